@@ -0,0 +1,436 @@
+//! 可替换的数量整数类型
+
+use alloc::string::String;
+use core::fmt;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+#[cfg(feature = "u256")]
+use primitive_types::U256;
+
+use crate::{OverflowStage, PairRate, PairRateError, MAX_DECIMAL_DIFF};
+
+/// 可用作代币数量的无符号整数类型
+///
+/// 换算在内部统一转为 `BigUint` 进行，只有最终结果需要收窄回 `Self`。
+pub trait UnsignedInteger: Clone + PartialOrd + fmt::Debug {
+    /// 乘法，溢出时返回 `None`
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    /// 乘方，溢出时返回 `None`
+    fn checked_pow(&self, exp: u32) -> Option<Self>;
+
+    /// 转换为 `BigUint`
+    fn to_biguint(&self) -> BigUint;
+
+    /// 由 `BigUint` 转换，超出表示范围时返回 `None`
+    fn from_biguint(value: &BigUint) -> Option<Self>;
+}
+
+macro_rules! impl_unsigned_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl UnsignedInteger for $ty {
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$ty>::checked_mul(*self, *other)
+                }
+
+                fn checked_pow(&self, exp: u32) -> Option<Self> {
+                    <$ty>::checked_pow(*self, exp)
+                }
+
+                fn to_biguint(&self) -> BigUint {
+                    BigUint::from(*self)
+                }
+
+                fn from_biguint(value: &BigUint) -> Option<Self> {
+                    <$ty>::try_from(value).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned_integer!(u8, u16, u32, u64, u128);
+
+impl UnsignedInteger for BigUint {
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn checked_pow(&self, exp: u32) -> Option<Self> {
+        Some(self.pow(exp))
+    }
+
+    fn to_biguint(&self) -> BigUint {
+        self.clone()
+    }
+
+    fn from_biguint(value: &BigUint) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+#[cfg(feature = "u256")]
+impl UnsignedInteger for U256 {
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        U256::checked_mul(*self, *other)
+    }
+
+    fn checked_pow(&self, exp: u32) -> Option<Self> {
+        U256::checked_pow(*self, U256::from(exp))
+    }
+
+    fn to_biguint(&self) -> BigUint {
+        let mut bytes = [0u8; 32];
+        self.to_little_endian(&mut bytes);
+        BigUint::from_bytes_le(&bytes)
+    }
+
+    fn from_biguint(value: &BigUint) -> Option<Self> {
+        let bytes = value.to_bytes_le();
+        if bytes.len() > 32 {
+            return None;
+        }
+        Some(U256::from_little_endian(&bytes))
+    }
+}
+
+impl PairRate {
+    /// 以 `BigUint` 数量计算输出代币数量，结果不收窄到 u128
+    ///
+    /// 截断规则与 `calculate_output_amount` 相同（先按汇率乘除取整，再调整精度），
+    /// 适用于整币供应量的最小单位数量超出 u128 的高精度代币。
+    pub fn calculate_output_amount_big(
+        price: &PairRate,
+        input_amount: BigUint,
+    ) -> Result<BigUint, PairRateError> {
+        if input_amount.is_zero() {
+            return Err(PairRateError::ZeroInputAmount);
+        }
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
+
+        let output_amount = big_output_amount(
+            input_amount,
+            (BigUint::from(price.rate.0), BigUint::from(price.rate.1)),
+            price.decimals,
+        );
+        if output_amount.is_zero() {
+            return Err(PairRateError::ZeroCalculatedOutput);
+        }
+        Ok(output_amount)
+    }
+
+    /// 以 `BigUint` 数量计算所需的输入代币数量，结果不收窄到 u128
+    ///
+    /// 截断规则与 `calculate_input_amount` 相同。
+    pub fn calculate_input_amount_big(
+        price: &PairRate,
+        output_amount: BigUint,
+    ) -> Result<BigUint, PairRateError> {
+        if output_amount.is_zero() {
+            return Err(PairRateError::ZeroOutputAmount);
+        }
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
+
+        let input_amount = big_input_amount(
+            output_amount,
+            (BigUint::from(price.rate.0), BigUint::from(price.rate.1)),
+            price.decimals,
+        );
+        if input_amount.is_zero() {
+            return Err(PairRateError::ZeroCalculatedInput);
+        }
+        Ok(input_amount)
+    }
+
+    /// 计算输出代币数量，结果超出 u128 时饱和为 `u128::MAX`，仅用于界面估算
+    ///
+    /// 输入为 0、输出截断为 0 或交易对无效时返回 0。结果不能区分溢出与真实数值，
+    /// 切勿用于结算。
+    pub fn calculate_output_amount_saturating(price: &PairRate, input_amount: u128) -> u128 {
+        match Self::calculate_output_amount_big(price, BigUint::from(input_amount)) {
+            Ok(output_amount) => u128::try_from(&output_amount).unwrap_or(u128::MAX),
+            Err(_) => 0,
+        }
+    }
+}
+
+impl<T: UnsignedInteger> PairRate<T> {
+    /// 创建以 `T` 为汇率与数量类型的价格对，带验证
+    ///
+    /// 换算全程以 `BigUint` 进行，汇率分量只要求非零，不受 `MAX_RATE` 限制；
+    /// 精度校验与 `new` 相同，精度差上限 `MAX_DECIMAL_DIFF` 与 u128 版本一样在换算时检查。
+    pub fn new_generic(
+        token_pair: (String, String),
+        rate: (T, T),
+        decimals: (u8, u8),
+    ) -> Result<Self, PairRateError> {
+        PairRate::validate_decimals(decimals)?;
+        let price = PairRate {
+            token_pair,
+            rate,
+            decimals,
+        };
+        price.big_rate()?;
+        Ok(price)
+    }
+
+    /// 以 `T` 类型的数量计算输出代币数量
+    ///
+    /// 截断规则与 `calculate_output_amount` 相同，中间值不受 `T` 的范围限制；
+    /// 结果超出 `T` 时返回 `OverflowStage::FinalNarrowing` 阶段的溢出错误。
+    pub fn calculate_output_amount_generic(
+        price: &PairRate<T>,
+        input_amount: &T,
+    ) -> Result<T, PairRateError> {
+        let input_amount = input_amount.to_biguint();
+        if input_amount.is_zero() {
+            return Err(PairRateError::ZeroInputAmount);
+        }
+        PairRate::validate_decimals(price.decimals)?;
+        validate_decimal_diff(price.decimals)?;
+
+        let output_amount = big_output_amount(input_amount, price.big_rate()?, price.decimals);
+        if output_amount.is_zero() {
+            return Err(PairRateError::ZeroCalculatedOutput);
+        }
+        narrow(&output_amount)
+    }
+
+    /// 以 `T` 类型的数量计算所需的输入代币数量
+    ///
+    /// 截断规则与 `calculate_input_amount` 相同，结果超出 `T` 时返回溢出错误。
+    pub fn calculate_input_amount_generic(
+        price: &PairRate<T>,
+        output_amount: &T,
+    ) -> Result<T, PairRateError> {
+        let output_amount = output_amount.to_biguint();
+        if output_amount.is_zero() {
+            return Err(PairRateError::ZeroOutputAmount);
+        }
+        PairRate::validate_decimals(price.decimals)?;
+        validate_decimal_diff(price.decimals)?;
+
+        let input_amount = big_input_amount(output_amount, price.big_rate()?, price.decimals);
+        if input_amount.is_zero() {
+            return Err(PairRateError::ZeroCalculatedInput);
+        }
+        narrow(&input_amount)
+    }
+
+    /// 以 `BigUint` 表示的汇率，任一分量为 0 时报错
+    fn big_rate(&self) -> Result<(BigUint, BigUint), PairRateError> {
+        let rate = (self.rate.0.to_biguint(), self.rate.1.to_biguint());
+        if rate.0.is_zero() || rate.1.is_zero() {
+            return Err(PairRateError::ZeroRate);
+        }
+        Ok(rate)
+    }
+}
+
+/// 检查精度差不超过 `MAX_DECIMAL_DIFF`，与 u128 版本的精度调整保持一致
+fn validate_decimal_diff(decimals: (u8, u8)) -> Result<(), PairRateError> {
+    let diff = decimals.0.abs_diff(decimals.1);
+    if diff > MAX_DECIMAL_DIFF {
+        return Err(PairRateError::DecimalDiffTooLarge {
+            diff,
+            max: MAX_DECIMAL_DIFF,
+        });
+    }
+    Ok(())
+}
+
+/// 按汇率乘除后调整精度（均截断）
+fn big_output_amount(
+    input_amount: BigUint,
+    rate: (BigUint, BigUint),
+    decimals: (u8, u8),
+) -> BigUint {
+    big_convert(input_amount * rate.1 / rate.0, decimals.0, decimals.1)
+}
+
+/// 按汇率反向乘除后调整精度（均截断）
+fn big_input_amount(
+    output_amount: BigUint,
+    rate: (BigUint, BigUint),
+    decimals: (u8, u8),
+) -> BigUint {
+    big_convert(output_amount * rate.0 / rate.1, decimals.1, decimals.0)
+}
+
+/// 将 `BigUint` 数量从 `from_decimals` 调整到 `to_decimals`（截断）
+fn big_convert(amount: BigUint, from_decimals: u8, to_decimals: u8) -> BigUint {
+    let scale = BigUint::from(10u8).pow(from_decimals.abs_diff(to_decimals) as u32);
+    if to_decimals >= from_decimals {
+        amount * scale
+    } else {
+        amount / scale
+    }
+}
+
+/// 将结果收窄到 `T`
+fn narrow<T: UnsignedInteger>(amount: &BigUint) -> Result<T, PairRateError> {
+    T::from_biguint(amount).ok_or(PairRateError::Overflow {
+        stage: OverflowStage::FinalNarrowing,
+        operand_bits: (amount.bits() as u32, 0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_generic_pair_rate() {
+        let token_pair = || ("TOKEN_A".to_string(), "TOKEN_B".to_string());
+
+        // PairRate 即 PairRate<u128>，结果与 u128 版本一致
+        let price: PairRate<u128> = PairRate::new(token_pair(), (10, 19), (18, 6)).unwrap();
+        for input in [1_000_000_000_000u128, 123_456_789_012_345_678_901] {
+            assert_eq!(
+                PairRate::calculate_output_amount_generic(&price, &input),
+                PairRate::calculate_output_amount(&price, input)
+            );
+        }
+        assert_eq!(
+            PairRate::calculate_input_amount_generic(&price, &1_900_000),
+            PairRate::calculate_input_amount(&price, 1_900_000)
+        );
+
+        // u64 数量与汇率
+        let price = PairRate::<u64>::new_generic(token_pair(), (10, 19), (18, 6)).unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_generic(&price, &5_000_000_000_000u64),
+            Ok(9)
+        );
+        assert_eq!(
+            PairRate::calculate_input_amount_generic(&price, &9u64),
+            Ok(4_000_000_000_000)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_generic(&price, &0),
+            Err(PairRateError::ZeroInputAmount)
+        );
+
+        // 中间值可以超出 u64，结果超出时报错
+        let price = PairRate::<u64>::new_generic(token_pair(), (1, 1_000), (6, 18)).unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_generic(&price, &u64::MAX),
+            Err(PairRateError::Overflow {
+                stage: OverflowStage::FinalNarrowing,
+                operand_bits: (114, 0)
+            })
+        );
+        assert_eq!(
+            PairRate::calculate_input_amount_generic(&price, &u64::MAX),
+            Ok(18_446)
+        );
+
+        // 精度差超过 MAX_DECIMAL_DIFF 时与 u128 版本一样报错
+        let price: PairRate<u128> = PairRate::new(token_pair(), (1, 1), (0, 38)).unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, 1),
+            Err(PairRateError::DecimalDiffTooLarge { diff: 38, max: 32 })
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_generic(&price, &1),
+            PairRate::calculate_output_amount(&price, 1)
+        );
+        assert_eq!(
+            PairRate::calculate_input_amount_generic(&price, &1),
+            Err(PairRateError::DecimalDiffTooLarge { diff: 38, max: 32 })
+        );
+
+        // BigUint 汇率不受 MAX_RATE 限制
+        let rate = BigUint::from(u128::MAX) * 4u8;
+        let price = PairRate::new_generic(token_pair(), (BigUint::from(1u8), rate.clone()), (0, 0))
+            .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_generic(&price, &BigUint::from(2u8)),
+            Ok(rate * 2u8)
+        );
+
+        assert_eq!(
+            PairRate::<u64>::new_generic(token_pair(), (0, 1), (18, 18)),
+            Err(PairRateError::ZeroRate)
+        );
+        assert!(PairRate::<u64>::new_generic(token_pair(), (1, 1), (39, 18)).is_err());
+    }
+
+    #[test]
+    fn test_calculate_amount_big() {
+        // 36 位精度代币：10^4 个整币的最小单位数量已超出 u128
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 36),
+        )
+        .unwrap();
+        let input = BigUint::from(10u8).pow(22);
+        let output = PairRate::calculate_output_amount_big(&price, input.clone()).unwrap();
+        assert_eq!(output, BigUint::from(2u8) * BigUint::from(10u8).pow(40));
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, 10u128.pow(22)),
+            Err(PairRateError::Overflow {
+                stage: OverflowStage::DecimalScaleUp,
+                operand_bits: (75, 60)
+            })
+        );
+        assert_eq!(
+            PairRate::calculate_input_amount_big(&price, output).unwrap(),
+            input
+        );
+
+        // 未超出 u128 时与 u128 版本一致
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+        for amount in [1_000_000u128, 123_456_789_012_345_678_901] {
+            assert_eq!(
+                PairRate::calculate_input_amount_big(&price, BigUint::from(amount)),
+                PairRate::calculate_input_amount(&price, amount).map(BigUint::from)
+            );
+            assert_eq!(
+                PairRate::calculate_output_amount_big(&price, BigUint::from(amount)),
+                PairRate::calculate_output_amount(&price, amount).map(BigUint::from)
+            );
+        }
+        assert_eq!(
+            PairRate::calculate_input_amount_big(&price, BigUint::zero()),
+            Err(PairRateError::ZeroOutputAmount)
+        );
+    }
+
+    #[test]
+    fn test_calculate_output_amount_saturating() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1_000),
+            (6, 18),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_saturating(&price, 1_000_000),
+            1_000_000_000_000_000_000_000
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_saturating(&price, u128::MAX / 2),
+            u128::MAX
+        );
+        assert!(PairRate::calculate_output_amount(&price, u128::MAX / 2).is_err());
+        assert_eq!(PairRate::calculate_output_amount_saturating(&price, 0), 0);
+
+        let invalid = PairRate {
+            rate: (0, 1),
+            ..price
+        };
+        assert_eq!(PairRate::calculate_output_amount_saturating(&invalid, 1), 0);
+    }
+}
@@ -0,0 +1,460 @@
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use crate::{PairRate, MAX_RATE};
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+#[cfg(feature = "bigint")]
+use num_integer::Roots;
+#[cfg(feature = "bigint")]
+use num_traits::{ToPrimitive, Zero};
+
+/// 价格簿：收集多个交易对的报价
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PriceBook {
+    pub pairs: Vec<PairRate>,
+}
+
+impl PriceBook {
+    /// 创建空的价格簿
+    pub fn new() -> Self {
+        Self { pairs: Vec::new() }
+    }
+
+    /// 添加一个交易对报价
+    pub fn insert(&mut self, pair: PairRate) {
+        self.pairs.push(pair);
+    }
+
+    /// 合并重复的交易对，汇率取算术平均
+    ///
+    /// 以 `token_pair`（有方向）作为规范键分组，保留每组首次出现的位置。
+    /// 同组内的精度必须一致，否则返回错误；平均汇率以最简分数表示，
+    /// 超出 `MAX_RATE` 时返回错误，价格簿保持不变。
+    pub fn dedup_average(&mut self) -> Result<(), String> {
+        let mut groups: Vec<(PairRate, Vec<(u128, u128)>)> = Vec::new();
+
+        for pair in &self.pairs {
+            match groups
+                .iter_mut()
+                .find(|(first, _)| first.token_pair == pair.token_pair)
+            {
+                Some((first, rates)) => {
+                    if first.decimals != pair.decimals {
+                        return Err(format!(
+                            "Decimals mismatch for pair {}/{}: {:?} vs {:?}",
+                            pair.token_pair.0, pair.token_pair.1, first.decimals, pair.decimals
+                        ));
+                    }
+                    rates.push(pair.rate);
+                }
+                None => groups.push((pair.clone(), vec![pair.rate])),
+            }
+        }
+
+        let mut merged = Vec::with_capacity(groups.len());
+        for (mut pair, rates) in groups {
+            pair.rate = Self::average_rate(&rates)?;
+            merged.push(pair);
+        }
+
+        self.pairs = merged;
+        Ok(())
+    }
+
+    /// 统计价格簿中互不相近的人类可读汇率个数
+    ///
+    /// 将汇率升序排列后贪心聚类：与当前簇最小汇率的偏差不超过 `tolerance_bps`
+    /// 基点的汇率归入同一簇，返回簇的数量。
+    #[cfg(feature = "std")]
+    pub fn distinct_rate_count(&self, tolerance_bps: u16) -> usize {
+        let mut rates: Vec<f64> = self
+            .pairs
+            .iter()
+            .map(PairRate::get_human_readable_rate)
+            .collect();
+        rates.sort_by(f64::total_cmp);
+
+        let tolerance = 1.0 + tolerance_bps as f64 / 10_000.0;
+        let mut count = 0;
+        let mut cluster_start = None;
+        for rate in rates {
+            match cluster_start {
+                Some(start) if rate <= start * tolerance => {}
+                _ => {
+                    cluster_start = Some(rate);
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// 计算所有以 `quote_token` 计价的交易对的人类可读汇率的几何平均值
+    ///
+    /// 返回 `(输入比率, 输出比率)`，表示两侧精度相同时的汇率。开方前分子分母
+    /// 各放大 `10^18`，整数开方保留 18 位有效精度；结果化简后超出 `MAX_RATE`
+    /// 时返回错误。
+    #[cfg(feature = "bigint")]
+    pub fn index_rate(&self, quote_token: &str) -> Result<(u128, u128), String> {
+        let quoted: Vec<&PairRate> = self
+            .pairs
+            .iter()
+            .filter(|pair| pair.token_pair.1 == quote_token)
+            .collect();
+        if quoted.is_empty() {
+            return Err(format!("No pairs quote into {}", quote_token));
+        }
+
+        let count = quoted.len() as u32;
+        let scale = BigUint::from(10u8).pow(18 * count);
+        let (numerator, denominator) =
+            quoted
+                .iter()
+                .fold((scale.clone(), scale), |(numerator, denominator), pair| {
+                    let (rate_numerator, rate_denominator) = pair.effective_rate_ratio();
+                    (numerator * rate_numerator, denominator * rate_denominator)
+                });
+
+        let numerator = Roots::nth_root(&numerator, count);
+        let denominator = Roots::nth_root(&denominator, count);
+        if numerator.is_zero() || denominator.is_zero() {
+            return Err("Index rate underflow".to_string());
+        }
+
+        let gcd = num_integer::Integer::gcd(&numerator, &denominator);
+        let rate = ((denominator / &gcd).to_u128(), (numerator / &gcd).to_u128());
+        match rate {
+            (Some(input_rate), Some(output_rate))
+                if input_rate <= MAX_RATE && output_rate <= MAX_RATE =>
+            {
+                Ok((input_rate, output_rate))
+            }
+            _ => Err(format!(
+                "Index rate components must be less than {}",
+                MAX_RATE
+            )),
+        }
+    }
+
+    /// 计算 `token` 相对于计价代币 `numeraire` 的人类可读汇率
+    ///
+    /// 在价格簿中按广度优先搜索最短路径（交易对可反向使用），沿路径 `compose`
+    /// 得到组合汇率，返回其最简 `(输入比率, 输出比率)` 表示。没有路径时返回错误。
+    pub fn rate_vs_numeraire(&self, token: &str, numeraire: &str) -> Result<(u128, u128), String> {
+        if token == numeraire {
+            return Ok((1, 1));
+        }
+
+        let route = self
+            .find_route(token, numeraire)
+            .ok_or_else(|| format!("No route from {} to {}", token, numeraire))?;
+        let (first, rest) = route.split_first().expect("route is never empty");
+        let composed = rest
+            .iter()
+            .try_fold(first.clone(), |composed, hop| composed.compose(hop))?;
+        composed.effective_rate()
+    }
+
+    /// 将一篮子代币（各自最小单位数量）换算为 `quote_token` 的最小单位数量并求和
+    ///
+    /// 每种代币按 `find_route` 的最短路径逐跳换算，相邻交易对的中间代币精度不一致时
+    /// 先调整精度；各路径结果统一换算到价格簿中 `quote_token` 首次出现时的精度。
+    /// 存在无法到达 `quote_token` 的代币时，返回列出所有这些代币的错误。
+    pub fn convert_basket(
+        &self,
+        inputs: &[(String, u128)],
+        quote_token: &str,
+    ) -> Result<u128, String> {
+        let quote_decimals = self.pairs.iter().find_map(|pair| {
+            if pair.token_pair.0 == quote_token {
+                Some(pair.decimals.0)
+            } else if pair.token_pair.1 == quote_token {
+                Some(pair.decimals.1)
+            } else {
+                None
+            }
+        });
+
+        let mut total = 0u128;
+        let mut unroutable: Vec<&str> = Vec::new();
+        for (token, amount) in inputs {
+            if token == quote_token {
+                total = total.checked_add(*amount).ok_or("Basket value overflow")?;
+                continue;
+            }
+            let (route, quote_decimals) =
+                match (self.find_route(token, quote_token), quote_decimals) {
+                    (Some(route), Some(quote_decimals)) => (route, quote_decimals),
+                    _ => {
+                        if !unroutable.contains(&token.as_str()) {
+                            unroutable.push(token);
+                        }
+                        continue;
+                    }
+                };
+
+            let mut value = *amount;
+            let mut decimals = route[0].decimals.0;
+            for hop in &route {
+                value = PairRate::adjust_decimals(value, decimals, hop.decimals.0)?;
+                value = PairRate::calculate_output_amount(hop, value)?;
+                decimals = hop.decimals.1;
+            }
+            value = PairRate::adjust_decimals(value, decimals, quote_decimals)?;
+            total = total.checked_add(value).ok_or("Basket value overflow")?;
+        }
+
+        if !unroutable.is_empty() {
+            return Err(format!(
+                "No route to {} for: {}",
+                quote_token,
+                unroutable.join(", ")
+            ));
+        }
+        Ok(total)
+    }
+
+    /// 按广度优先搜索从 `from` 到 `to` 的最短路径，返回沿途方向正确的交易对
+    pub(crate) fn find_route(&self, from: &str, to: &str) -> Option<Vec<PairRate>> {
+        let mut visited = vec![from.to_string()];
+        let mut queue = VecDeque::from([(from.to_string(), Vec::new())]);
+
+        while let Some((token, path)) = queue.pop_front() {
+            for pair in &self.pairs {
+                let hop = if pair.token_pair.0 == token {
+                    pair.clone()
+                } else if pair.token_pair.1 == token {
+                    pair.invert()
+                } else {
+                    continue;
+                };
+                if visited.contains(&hop.token_pair.1) {
+                    continue;
+                }
+
+                let next = hop.token_pair.1.clone();
+                let mut next_path: Vec<PairRate> = path.clone();
+                next_path.push(hop);
+                if next == to {
+                    return Some(next_path);
+                }
+                visited.push(next.clone());
+                queue.push_back((next, next_path));
+            }
+        }
+        None
+    }
+
+    /// 计算一组汇率（输出/输入）的精确算术平均值
+    pub(crate) fn average_rate(rates: &[(u128, u128)]) -> Result<(u128, u128), String> {
+        let overflow = || "Averaged rate overflow".to_string();
+
+        // 以 (分母, 分子) 累加 Σ rate.1 / rate.0
+        let mut sum = (1u128, 0u128);
+        for &(input_rate, output_rate) in rates {
+            if input_rate == 0 {
+                return Err("Rate components must be greater than 0".to_string());
+            }
+            let numerator = sum
+                .1
+                .checked_mul(input_rate)
+                .and_then(|a| {
+                    output_rate
+                        .checked_mul(sum.0)
+                        .and_then(|b| a.checked_add(b))
+                })
+                .ok_or_else(overflow)?;
+            let denominator = sum.0.checked_mul(input_rate).ok_or_else(overflow)?;
+            sum = PairRate::reduce_rate((denominator, numerator));
+        }
+
+        let count = rates.len() as u128;
+        let average =
+            PairRate::reduce_rate((sum.0.checked_mul(count).ok_or_else(overflow)?, sum.1));
+
+        if average.0 > MAX_RATE || average.1 > MAX_RATE {
+            return Err(format!(
+                "Averaged rate components must be less than {}",
+                MAX_RATE
+            ));
+        }
+
+        Ok(average)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_average() {
+        let mut book = PriceBook::new();
+        book.insert(
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (1, 2),
+                (18, 18),
+            )
+            .unwrap(),
+        );
+        book.insert(
+            PairRate::new(
+                ("TOKEN_B".to_string(), "TOKEN_C".to_string()),
+                (1, 1),
+                (18, 6),
+            )
+            .unwrap(),
+        );
+        book.insert(
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (2, 6),
+                (18, 18),
+            )
+            .unwrap(),
+        );
+
+        // (2 + 3) / 2 = 2.5
+        book.dedup_average().unwrap();
+        assert_eq!(book.pairs.len(), 2);
+        assert_eq!(book.pairs[0].token_pair.1, "TOKEN_B");
+        assert_eq!(book.pairs[0].rate, (2, 5));
+        assert_eq!(book.pairs[1].rate, (1, 1));
+
+        // 同组精度不一致
+        book.insert(
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (1, 2),
+                (18, 6),
+            )
+            .unwrap(),
+        );
+        assert!(book.dedup_average().is_err());
+        assert_eq!(book.pairs.len(), 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_distinct_rate_count() {
+        let mut book = PriceBook::new();
+        for rate in [
+            (10_000, 20_000),
+            (10_000, 20_010),
+            (10_000, 19_995),
+            (1, 3),
+            (1, 5),
+        ] {
+            book.insert(
+                PairRate::new(
+                    ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                    rate,
+                    (18, 18),
+                )
+                .unwrap(),
+            );
+        }
+
+        // 2.0、2.001、1.9995 在 10 基点内视为同一报价
+        assert_eq!(book.distinct_rate_count(10), 3);
+        assert_eq!(book.distinct_rate_count(0), 5);
+        assert_eq!(book.distinct_rate_count(10_000), 2);
+        assert_eq!(PriceBook::new().distinct_rate_count(10), 0);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_index_rate() {
+        let mut book = PriceBook::new();
+        for (base, rate, decimals) in [
+            ("TOKEN_A", (1, 1), (18, 18)),
+            ("TOKEN_B", (1, 2), (6, 6)),
+            ("TOKEN_C", (1, 4), (9, 9)),
+        ] {
+            book.insert(
+                PairRate::new((base.to_string(), "USD".to_string()), rate, decimals).unwrap(),
+            );
+        }
+        book.insert(
+            PairRate::new(
+                ("USD".to_string(), "TOKEN_A".to_string()),
+                (1, 100),
+                (18, 18),
+            )
+            .unwrap(),
+        );
+
+        // (1 * 2 * 4)^(1/3) = 2
+        assert_eq!(book.index_rate("USD").unwrap(), (1, 2));
+        assert!(book.index_rate("EUR").is_err());
+    }
+
+    #[test]
+    fn test_rate_vs_numeraire() {
+        let mut book = PriceBook::new();
+        // A -> B 汇率 2，USD -> B 汇率 4（需反向使用）
+        book.insert(
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (1, 2),
+                (18, 6),
+            )
+            .unwrap(),
+        );
+        book.insert(
+            PairRate::new(("USD".to_string(), "TOKEN_B".to_string()), (1, 4), (6, 6)).unwrap(),
+        );
+
+        // A -> B -> USD：2 * 0.25 = 0.5，精度 18 -> 6 使人类可读汇率再乘以 10^12
+        let rate = book.rate_vs_numeraire("TOKEN_A", "USD").unwrap();
+        assert_eq!(rate, (1, 500_000_000_000));
+        assert_eq!(book.rate_vs_numeraire("USD", "USD").unwrap(), (1, 1));
+        assert!(book.rate_vs_numeraire("TOKEN_A", "EUR").is_err());
+    }
+
+    #[test]
+    fn test_convert_basket() {
+        let mut book = PriceBook::new();
+        // 1 A = 2 USD，1 USD = 4 C，1 B = 3 A
+        book.insert(
+            PairRate::new(("TOKEN_A".to_string(), "USD".to_string()), (1, 2), (18, 6)).unwrap(),
+        );
+        book.insert(
+            PairRate::new(("USD".to_string(), "TOKEN_C".to_string()), (1, 4), (6, 6)).unwrap(),
+        );
+        book.insert(
+            PairRate::new(
+                ("TOKEN_B".to_string(), "TOKEN_A".to_string()),
+                (1, 3),
+                (8, 18),
+            )
+            .unwrap(),
+        );
+
+        // A: 2 USD，C: 2 USD，B -> A -> USD: 6 USD，另有 0.5 USD
+        let basket = [
+            ("TOKEN_A".to_string(), 1_000_000_000_000_000_000),
+            ("TOKEN_B".to_string(), 100_000_000),
+            ("TOKEN_C".to_string(), 8_000_000),
+            ("USD".to_string(), 500_000),
+        ];
+        assert_eq!(book.convert_basket(&basket, "USD").unwrap(), 10_500_000);
+        assert_eq!(book.convert_basket(&[], "USD").unwrap(), 0);
+
+        let err = book
+            .convert_basket(
+                &[
+                    ("TOKEN_X".to_string(), 1),
+                    ("TOKEN_A".to_string(), 1_000_000_000_000_000_000),
+                    ("TOKEN_Y".to_string(), 1),
+                ],
+                "USD",
+            )
+            .unwrap_err();
+        assert_eq!(err, "No route to USD for: TOKEN_X, TOKEN_Y");
+    }
+}
@@ -0,0 +1,130 @@
+use alloc::string::String;
+
+use crate::{PairRate, PairRateError};
+
+/// `PairRate` 的构建器，以具名方法代替 `new` 的位置参数
+///
+/// `rate` 与 `decimals` 必须设置；`token_pair` 未设置时默认报错，
+/// 调用 `allow_empty_token_pair` 后才以空字符串代替。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PairRateBuilder {
+    token_pair: Option<(String, String)>,
+    rate: Option<(u128, u128)>,
+    decimals: Option<(u8, u8)>,
+    allow_empty_token_pair: bool,
+}
+
+impl PairRateBuilder {
+    /// 创建未设置任何字段的构建器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置 (输入代币, 输出代币)
+    pub fn token_pair(mut self, input: impl Into<String>, output: impl Into<String>) -> Self {
+        self.token_pair = Some((input.into(), output.into()));
+        self
+    }
+
+    /// 设置 (输入比率, 输出比率)
+    pub fn rate(mut self, input: u128, output: u128) -> Self {
+        self.rate = Some((input, output));
+        self
+    }
+
+    /// 设置 (输入精度, 输出精度)
+    pub fn decimals(mut self, input: u8, output: u8) -> Self {
+        self.decimals = Some((input, output));
+        self
+    }
+
+    /// 允许不设置 `token_pair`，此时两侧代币均为空字符串
+    pub fn allow_empty_token_pair(mut self) -> Self {
+        self.allow_empty_token_pair = true;
+        self
+    }
+
+    /// 校验并创建 `PairRate`
+    pub fn build(self) -> Result<PairRate, PairRateError> {
+        let token_pair = match self.token_pair {
+            Some(token_pair) => token_pair,
+            None if self.allow_empty_token_pair => (String::new(), String::new()),
+            None => return Err(PairRateError::MissingField("token_pair")),
+        };
+        let rate = self.rate.ok_or(PairRateError::MissingField("rate"))?;
+        let decimals = self
+            .decimals
+            .ok_or(PairRateError::MissingField("decimals"))?;
+        PairRate::new(token_pair, rate, decimals)
+    }
+}
+
+impl PairRate {
+    /// 创建 `PairRateBuilder`
+    pub fn builder() -> PairRateBuilder {
+        PairRateBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pair_rate_builder() {
+        let price = PairRate::builder()
+            .token_pair("TOKEN_A", "TOKEN_B")
+            .rate(10, 19)
+            .decimals(18, 6)
+            .build()
+            .unwrap();
+        assert_eq!(
+            price,
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (10, 19),
+                (18, 6),
+            )
+            .unwrap()
+        );
+
+        // 缺少必填字段
+        assert_eq!(
+            PairRate::builder()
+                .token_pair("TOKEN_A", "TOKEN_B")
+                .decimals(18, 6)
+                .build(),
+            Err(PairRateError::MissingField("rate"))
+        );
+        assert_eq!(
+            PairRate::builder()
+                .token_pair("TOKEN_A", "TOKEN_B")
+                .rate(1, 1)
+                .build(),
+            Err(PairRateError::MissingField("decimals"))
+        );
+        assert_eq!(
+            PairRate::builder().rate(1, 1).decimals(18, 18).build(),
+            Err(PairRateError::MissingField("token_pair"))
+        );
+
+        // 显式允许空代币符号
+        let price = PairRate::builder()
+            .allow_empty_token_pair()
+            .rate(1, 1)
+            .decimals(18, 18)
+            .build()
+            .unwrap();
+        assert_eq!(price.token_pair, (String::new(), String::new()));
+
+        // 与 new 相同的校验
+        assert_eq!(
+            PairRate::builder()
+                .token_pair("TOKEN_A", "TOKEN_B")
+                .rate(0, 1)
+                .decimals(18, 18)
+                .build(),
+            Err(PairRateError::ZeroRate)
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{math, PairRate, ZeroPolicy};
+
+/// 分段线性价格曲线：按输入数量在相邻报价点之间插值汇率
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceCurve {
+    pub token_pair: (String, String),
+    /// 按输入阈值严格递增排列的 `(输入阈值, 汇率)` 点
+    pub points: Vec<(u128, (u128, u128))>,
+    pub decimals: (u8, u8),
+}
+
+impl PriceCurve {
+    /// 创建价格曲线，要求报价点非空、阈值严格递增且每个汇率有效
+    pub fn new(
+        token_pair: (String, String),
+        points: Vec<(u128, (u128, u128))>,
+        decimals: (u8, u8),
+    ) -> Result<Self, String> {
+        if points.is_empty() {
+            return Err("Price curve must contain at least one point".to_string());
+        }
+        if points.windows(2).any(|w| w[0].0 >= w[1].0) {
+            return Err("Price curve thresholds must be strictly increasing".to_string());
+        }
+        for &(_, rate) in &points {
+            PairRate::new(token_pair.clone(), rate, decimals)?;
+        }
+
+        Ok(Self {
+            token_pair,
+            points,
+            decimals,
+        })
+    }
+
+    /// 按插值后的汇率计算输出代币数量
+    ///
+    /// 输入低于第一个阈值或高于最后一个阈值时使用端点汇率。由于输出与汇率成线性关系，
+    /// 插值在两端点汇率各自的输出之间进行，结果向下取整。
+    pub fn calculate_output(&self, input_amount: u128) -> Result<u128, String> {
+        let upper = self
+            .points
+            .iter()
+            .position(|&(threshold, _)| threshold > input_amount);
+
+        let (lower_point, upper_point) = match upper {
+            Some(0) => return self.output_at(0, input_amount),
+            None => return self.output_at(self.points.len() - 1, input_amount),
+            Some(index) => (self.points[index - 1], self.points[index]),
+        };
+
+        let lower_output = self.output_at_rate(lower_point.1, input_amount)?;
+        let upper_output = self.output_at_rate(upper_point.1, input_amount)?;
+        let span = upper_point.0 - lower_point.0;
+        let offset = input_amount - lower_point.0;
+        let overflow = || "Interpolation overflow".to_string();
+
+        let output_amount = if upper_output >= lower_output {
+            let delta = math::mul_div_floor(upper_output - lower_output, offset, span)
+                .ok_or_else(overflow)?;
+            lower_output + delta
+        } else {
+            let delta = math::mul_div_ceil(lower_output - upper_output, offset, span)
+                .ok_or_else(overflow)?;
+            lower_output - delta
+        };
+
+        if output_amount == 0 {
+            return Err("Calculated output amount is zero, increase input amount".to_string());
+        }
+        Ok(output_amount)
+    }
+
+    /// 使用第 `index` 个报价点的汇率计算输出
+    fn output_at(&self, index: usize, input_amount: u128) -> Result<u128, String> {
+        let price = PairRate::new(self.token_pair.clone(), self.points[index].1, self.decimals)?;
+        PairRate::calculate_output_amount(&price, input_amount).map_err(String::from)
+    }
+
+    /// 使用给定汇率计算输出，结果不足一个最小单位时返回 0
+    fn output_at_rate(&self, rate: (u128, u128), input_amount: u128) -> Result<u128, String> {
+        let price = PairRate::new(self.token_pair.clone(), rate, self.decimals)?;
+        PairRate::calculate_output_amount_zero_policy(&price, input_amount, ZeroPolicy::ReturnZero)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> PriceCurve {
+        PriceCurve::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            vec![(1_000, (1, 2)), (3_000, (1, 4)), (5_000, (1, 3))],
+            (18, 18),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_price_curve_at_points() {
+        let curve = curve();
+        assert_eq!(curve.calculate_output(1_000).unwrap(), 2_000);
+        assert_eq!(curve.calculate_output(3_000).unwrap(), 12_000);
+        assert_eq!(curve.calculate_output(5_000).unwrap(), 15_000);
+
+        // 超出端点时使用端点汇率
+        assert_eq!(curve.calculate_output(500).unwrap(), 1_000);
+        assert_eq!(curve.calculate_output(6_000).unwrap(), 18_000);
+    }
+
+    #[test]
+    fn test_price_curve_between_points() {
+        let curve = curve();
+        // 2000 处汇率插值为 3：2000 * 3 = 6000
+        assert_eq!(curve.calculate_output(2_000).unwrap(), 6_000);
+        // 4000 处汇率插值为 3.5：4000 * 3.5 = 14000
+        assert_eq!(curve.calculate_output(4_000).unwrap(), 14_000);
+    }
+
+    #[test]
+    fn test_price_curve_validation() {
+        let pair = ("TOKEN_A".to_string(), "TOKEN_B".to_string());
+        assert!(PriceCurve::new(pair.clone(), vec![], (18, 18)).is_err());
+        assert!(PriceCurve::new(pair.clone(), vec![(2, (1, 1)), (1, (1, 2))], (18, 18)).is_err());
+        assert!(PriceCurve::new(pair.clone(), vec![(1, (1, 1)), (1, (1, 2))], (18, 18)).is_err());
+        assert!(PriceCurve::new(pair, vec![(1, (0, 1))], (18, 18)).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! 交易对计算的错误类型
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::fmt;
+
+/// 交易对创建与换算过程中的错误
+///
+/// `Display` 输出与此前返回的字符串错误保持一致。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairRateError {
+    /// 输入数量为 0
+    ZeroInputAmount,
+    /// 输出数量为 0
+    ZeroOutputAmount,
+    /// 换算得到的输出数量为 0
+    ZeroCalculatedOutput,
+    /// 反算得到的输入数量为 0
+    ZeroCalculatedInput,
+    /// 汇率分量为 0
+    ZeroRate,
+    /// 汇率分量超出上限
+    RateOutOfRange { value: u128, max: u128 },
+    /// 输入精度超出上限
+    InputDecimalsExceedMax { value: u8, max: u8 },
+    /// 输出精度超出上限
+    OutputDecimalsExceedMax { value: u8, max: u8 },
+    /// 精度调整时精度超出上限
+    DecimalsExceedMax { value: u8, max: u8 },
+    /// 精度差超出上限
+    DecimalDiffTooLarge { diff: u8, max: u8 },
+    /// 数值溢出，`stage` 指出溢出发生的计算阶段，`operand_bits` 为该阶段两个操作数的有效位数
+    ///
+    /// 收窄阶段只有一个操作数（待收窄的结果），第二项为 0。
+    Overflow {
+        stage: OverflowStage,
+        operand_bits: (u32, u32),
+    },
+    /// 乘积位数超出调用方设定的上限
+    OperandTooLarge { bits: u32, max_bits: u32 },
+    /// 除数为 0
+    DivisionByZero,
+    /// 手续费基点超出上限
+    FeeOutOfRange { fee_bps: u16, max: u16 },
+    /// 滑点基点超出上限
+    SlippageOutOfRange { slippage_bps: u16, max: u16 },
+    /// 构建器缺少必填字段
+    MissingField(&'static str),
+    /// 输出低于调用方要求的最小值
+    SlippageExceeded { got: u128, min: u128 },
+    /// 所需输入超出调用方允许的最大值
+    MaxInputExceeded { required: u128, max: u128 },
+    /// 代币符号无效（为空或两侧相同）
+    InvalidTokenSymbol(&'static str),
+    /// 字符串格式无法解析
+    InvalidFormat,
+    /// 路径为空
+    EmptyRoute,
+    /// 路径第 `at_index` 跳的输出代币与下一跳的输入代币不一致
+    RouteMismatch { at_index: usize },
+    /// 组合交易对时中间代币的两侧精度不一致
+    IntermediateDecimalsMismatch { output: u8, input: u8 },
+    /// 批量计算中第 `index` 个输入出错
+    AtIndex {
+        index: usize,
+        error: Box<PairRateError>,
+    },
+}
+
+/// 溢出发生的计算阶段
+///
+/// 调用方可据此决定处理方式：预检查或乘除阶段溢出时减小数量，精度放大阶段溢出时
+/// 调整精度，收窄阶段溢出时改用更宽的数量类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowStage {
+    /// 乘除运算前的范围预检查：操作数超出 `MAX_RATE`
+    RatePrecheck,
+    /// 按汇率乘除的结果超出 u128
+    MultiplyDivide,
+    /// 精度提高时乘以 `10^diff` 溢出
+    DecimalScaleUp,
+    /// 以更宽类型计算的最终结果无法收窄到目标类型
+    FinalNarrowing,
+}
+
+impl fmt::Display for OverflowStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OverflowStage::RatePrecheck => "rate precheck",
+            OverflowStage::MultiplyDivide => "multiply-divide",
+            OverflowStage::DecimalScaleUp => "decimal scale-up",
+            OverflowStage::FinalNarrowing => "final narrowing",
+        };
+        f.write_str(name)
+    }
+}
+
+impl PairRateError {
+    /// 构造溢出错误，记录两个操作数的有效位数
+    pub(crate) fn overflow(stage: OverflowStage, operands: (u128, u128)) -> Self {
+        let bits = |value: u128| 128 - value.leading_zeros();
+        PairRateError::Overflow {
+            stage,
+            operand_bits: (bits(operands.0), bits(operands.1)),
+        }
+    }
+
+    /// 是否为数值溢出类错误
+    pub fn is_overflow(&self) -> bool {
+        match self {
+            PairRateError::AtIndex { error, .. } => error.is_overflow(),
+            _ => matches!(self, PairRateError::Overflow { .. }),
+        }
+    }
+}
+
+impl fmt::Display for PairRateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PairRateError::ZeroInputAmount => f.write_str("Input amount must be greater than 0"),
+            PairRateError::ZeroOutputAmount => f.write_str("Output amount must be greater than 0"),
+            PairRateError::ZeroCalculatedOutput => {
+                f.write_str("Calculated output amount is zero, increase input amount")
+            }
+            PairRateError::ZeroCalculatedInput => {
+                f.write_str("Calculated input amount is zero, increase output amount")
+            }
+            PairRateError::ZeroRate => f.write_str("Rate components must be greater than 0"),
+            PairRateError::RateOutOfRange { max, .. } => {
+                write!(f, "Rate components must be less than {}", max)
+            }
+            PairRateError::InputDecimalsExceedMax { value, max } => {
+                write!(
+                    f,
+                    "Input decimals {} exceeds maximum allowed {}",
+                    value, max
+                )
+            }
+            PairRateError::OutputDecimalsExceedMax { value, max } => {
+                write!(
+                    f,
+                    "Output decimals {} exceeds maximum allowed {}",
+                    value, max
+                )
+            }
+            PairRateError::DecimalsExceedMax { max, .. } => {
+                write!(f, "Decimals must be less than or equal to {}", max)
+            }
+            PairRateError::DecimalDiffTooLarge { diff, max } => write!(
+                f,
+                "Decimal difference {} exceeds maximum allowed {}, scale amounts by 10^{} first",
+                diff,
+                max,
+                diff.saturating_sub(*max)
+            ),
+            PairRateError::Overflow {
+                stage,
+                operand_bits: (lhs_bits, rhs_bits),
+            } => write!(
+                f,
+                "{} (overflow at {} step, operands of {} and {} bits)",
+                match stage {
+                    OverflowStage::RatePrecheck => "Input values too large for safe calculation",
+                    OverflowStage::MultiplyDivide => "Result exceeds u128",
+                    OverflowStage::DecimalScaleUp => "Decimal adjustment caused overflow",
+                    OverflowStage::FinalNarrowing => "Result does not fit in the target type",
+                },
+                stage,
+                lhs_bits,
+                rhs_bits
+            ),
+            PairRateError::OperandTooLarge { bits, max_bits } => write!(
+                f,
+                "Operands need {} bits, exceeding limit {}",
+                bits, max_bits
+            ),
+            PairRateError::DivisionByZero => f.write_str("Division by zero"),
+            PairRateError::FeeOutOfRange { fee_bps, max } => {
+                write!(f, "Fee {} bps exceeds maximum allowed {}", fee_bps, max)
+            }
+            PairRateError::SlippageOutOfRange { slippage_bps, max } => write!(
+                f,
+                "Slippage {} bps exceeds maximum allowed {}",
+                slippage_bps, max
+            ),
+            PairRateError::MissingField(field) => write!(f, "Missing required field: {}", field),
+            PairRateError::SlippageExceeded { got, min } => {
+                write!(f, "Output amount {} is below minimum {}", got, min)
+            }
+            PairRateError::MaxInputExceeded { required, max } => write!(
+                f,
+                "Required input amount {} exceeds maximum {}",
+                required, max
+            ),
+            PairRateError::InvalidTokenSymbol(reason) => {
+                write!(f, "Invalid token symbol: {}", reason)
+            }
+            PairRateError::InvalidFormat => {
+                f.write_str("Invalid pair rate format, expected A/B=<rate>@<dec_in>:<dec_out>")
+            }
+            PairRateError::EmptyRoute => f.write_str("Route must contain at least one pair"),
+            PairRateError::RouteMismatch { at_index } => write!(
+                f,
+                "Route is not connected between hops {} and {}",
+                at_index,
+                at_index + 1
+            ),
+            PairRateError::IntermediateDecimalsMismatch { output, input } => write!(
+                f,
+                "Intermediate token decimals differ: {} vs {}",
+                output, input
+            ),
+            PairRateError::AtIndex { index, error } => write!(f, "Input {}: {}", index, error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PairRateError {}
+
+impl From<PairRateError> for String {
+    fn from(error: PairRateError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_matches_legacy_messages() {
+        assert_eq!(
+            PairRateError::ZeroInputAmount.to_string(),
+            "Input amount must be greater than 0"
+        );
+        assert_eq!(
+            PairRateError::RateOutOfRange {
+                value: u128::MAX,
+                max: 7
+            }
+            .to_string(),
+            "Rate components must be less than 7"
+        );
+        assert_eq!(
+            PairRateError::DecimalDiffTooLarge { diff: 33, max: 32 }.to_string(),
+            "Decimal difference 33 exceeds maximum allowed 32, scale amounts by 10^1 first"
+        );
+        assert_eq!(
+            String::from(PairRateError::DivisionByZero),
+            "Division by zero"
+        );
+        let overflow = PairRateError::overflow(OverflowStage::DecimalScaleUp, (u128::MAX, 1_000));
+        assert!(overflow.is_overflow());
+        assert_eq!(
+            overflow.to_string(),
+            "Decimal adjustment caused overflow (overflow at decimal scale-up step, operands of 128 and 10 bits)"
+        );
+        assert!(!PairRateError::ZeroRate.is_overflow());
+    }
+}
@@ -1,45 +1,89 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt;
+#[cfg(feature = "bigint")]
 use num_bigint::BigUint;
+use num_integer::{Integer, Roots};
+#[cfg(feature = "rational")]
+use num_rational::Ratio;
+#[cfg(feature = "bigint")]
 use num_traits::ToPrimitive;
+#[cfg(feature = "u256")]
+use primitive_types::U256;
+
+#[cfg(feature = "bigint")]
+mod amount;
+mod book;
+mod builder;
+mod curve;
+mod error;
+mod math;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod wrappers;
+
+#[cfg(feature = "bigint")]
+pub use amount::UnsignedInteger;
+pub use book::PriceBook;
+pub use builder::PairRateBuilder;
+pub use curve::PriceCurve;
+pub use error::{OverflowStage, PairRateError};
+pub use wrappers::{BandedPair, MonotonicPair, VolumeCappedPair};
 
 /// 常量定义
 pub const MAX_DECIMALS: u8 = 38; // 最大支持的精度
 pub const MIN_RATE: u128 = 1; // 最小汇率
 pub const MAX_RATE: u128 = u128::MAX / 2; // 最大安全汇率
-pub const MAX_DECIMAL_DIFF: u8 = 32; // 最大精度差
+pub const MAX_DECIMAL_DIFF: u8 = 32; // 最大精度差（含）
+pub const MAX_FEE_BPS: u16 = 10_000; // 最大手续费（基点）
+pub const BPS_DENOMINATOR: u16 = 10_000; // 基点换算为比例的分母
 
 /// 价格信息结构体
-#[derive(Debug, Clone, PartialEq)]
-pub struct PairRate {
+///
+/// `T` 为汇率与数量的整数类型，默认 `u128`，`PairRate` 即 `PairRate<u128>`。
+/// 其他类型（需 `bigint` 特性）通过 `UnsignedInteger` 实现的 `*_generic` 方法构造与换算。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PairRate<T = u128> {
     pub token_pair: (String, String),
-    pub rate: (u128, u128),
+    pub rate: (T, T),
     pub decimals: (u8, u8),
 }
 
 impl PairRate {
     /// 验证精度是否在有效范围内
-    fn validate_decimals(decimals: (u8, u8)) -> Result<(), String> {
+    fn validate_decimals(decimals: (u8, u8)) -> Result<(), PairRateError> {
         if decimals.0 > MAX_DECIMALS {
-            return Err(format!(
-                "Input decimals {} exceeds maximum allowed {}",
-                decimals.0, MAX_DECIMALS
-            ));
+            return Err(PairRateError::InputDecimalsExceedMax {
+                value: decimals.0,
+                max: MAX_DECIMALS,
+            });
         }
         if decimals.1 > MAX_DECIMALS {
-            return Err(format!(
-                "Output decimals {} exceeds maximum allowed {}",
-                decimals.1, MAX_DECIMALS
-            ));
+            return Err(PairRateError::OutputDecimalsExceedMax {
+                value: decimals.1,
+                max: MAX_DECIMALS,
+            });
         }
         Ok(())
     }
 
     /// 验证汇率是否在安全范围内
-    fn validate_rate(rate: (u128, u128)) -> Result<(), String> {
+    fn validate_rate(rate: (u128, u128)) -> Result<(), PairRateError> {
         if rate.0 < MIN_RATE || rate.1 < MIN_RATE {
-            return Err("Rate components must be greater than 0".to_string());
+            return Err(PairRateError::ZeroRate);
         }
         if rate.0 > MAX_RATE || rate.1 > MAX_RATE {
-            return Err(format!("Rate components must be less than {}", MAX_RATE));
+            return Err(PairRateError::RateOutOfRange {
+                value: rate.0.max(rate.1),
+                max: MAX_RATE,
+            });
         }
         Ok(())
     }
@@ -49,7 +93,7 @@ impl PairRate {
         token_pair: (String, String),
         rate: (u128, u128),
         decimals: (u8, u8),
-    ) -> Result<Self, String> {
+    ) -> Result<Self, PairRateError> {
         Self::validate_decimals(decimals)?;
         Self::validate_rate(rate)?;
 
@@ -60,474 +104,6568 @@ impl PairRate {
         })
     }
 
+    /// 由十进制汇率字符串（如 `"2.5"`、`"0.000001"`）精确创建价格对
+    ///
+    /// 字符串与 `get_human_readable_rate` 的含义相同，即 `rate.1 * 10^decimals.0 /
+    /// (rate.0 * 10^decimals.1)`，精确解析为分数后按精度换算并化简，不经过 f64。
+    /// 结果是 `get_human_readable_rate` 的无损逆运算。
+    pub fn from_human_readable(
+        token_pair: (String, String),
+        price: &str,
+        decimals: (u8, u8),
+    ) -> Result<PairRate, PairRateError> {
+        Self::validate_decimals(decimals)?;
+        let (input_rate, output_rate) = Self::parse_rate(price)?;
+        let (input_decimals, output_decimals) = decimals;
+
+        // rate.1 / rate.0 = output_rate * 10^decimals.1 / (input_rate * 10^decimals.0)，
+        // 先约去公共的 10 的幂，再与另一侧约分（parse_rate 的结果已互质）
+        let scale_up = |rate: u128, other: u128, diff: u8| {
+            let scale = 10u128.pow(diff as u32);
+            let gcd = other.gcd(&scale);
+            rate.checked_mul(scale / gcd)
+                .map(|rate| (rate, other / gcd))
+                .ok_or(PairRateError::RateOutOfRange {
+                    value: u128::MAX,
+                    max: MAX_RATE,
+                })
+        };
+        let rate = if input_decimals >= output_decimals {
+            scale_up(input_rate, output_rate, input_decimals - output_decimals)?
+        } else {
+            let (output_rate, input_rate) =
+                scale_up(output_rate, input_rate, output_decimals - input_decimals)?;
+            (input_rate, output_rate)
+        };
+        Self::new(token_pair, rate, decimals)
+    }
+
+    /// 在常量上下文中构造价格对，代币符号为空字符串
+    ///
+    /// **警告：此函数不做任何校验。** 零汇率、超出 `MAX_RATE` 的汇率或超出 `MAX_DECIMALS`
+    /// 的精度都会被原样接受，错误只会在之后的计算中暴露。仅用于编译期已知正确的参考汇率，
+    /// 例如 `const RATE: PairRate = PairRate::new_unchecked((1, 2), (18, 18));`；
+    /// 需要代币符号时可在运行时克隆后赋值 `token_pair`。
+    pub const fn new_unchecked(rate: (u128, u128), decimals: (u8, u8)) -> PairRate {
+        PairRate {
+            token_pair: (String::new(), String::new()),
+            rate,
+            decimals,
+        }
+    }
+
+    /// 与 `new` 相同，但额外要求两个代币符号非空（忽略空白）且互不相同
+    ///
+    /// `wrap` 等同币种的换算不适用此构造函数。
+    pub fn new_strict(
+        token_pair: (String, String),
+        rate: (u128, u128),
+        decimals: (u8, u8),
+    ) -> Result<Self, PairRateError> {
+        if token_pair.0.trim().is_empty() {
+            return Err(PairRateError::InvalidTokenSymbol("input token is empty"));
+        }
+        if token_pair.1.trim().is_empty() {
+            return Err(PairRateError::InvalidTokenSymbol("output token is empty"));
+        }
+        if token_pair.0 == token_pair.1 {
+            return Err(PairRateError::InvalidTokenSymbol(
+                "input and output tokens are identical",
+            ));
+        }
+        Self::new(token_pair, rate, decimals)
+    }
+
+    /// 由对数价格创建价格对（需要 `std` 特性），汇率（输出/输入）为 `base^log_price`
+    ///
+    /// 以 `10^k` 作为输入比率（`k` 不超过 18，且保证输出比率不超过 `MAX_RATE`），
+    /// 输出比率四舍五入后化简。`base` 须不小于 2；结果非有限、超出 `MAX_RATE`
+    /// 或舍入为 0 时返回错误。
+    #[cfg(feature = "std")]
+    pub fn from_log_price(
+        token_pair: (String, String),
+        log_price: f64,
+        base: u32,
+        decimals: (u8, u8),
+    ) -> Result<Self, String> {
+        const MAX_SCALE_EXP: i32 = 18;
+
+        if base < 2 {
+            return Err("Log price base must be at least 2".to_string());
+        }
+        let price = (base as f64).powf(log_price);
+        if !price.is_finite() || price > MAX_RATE as f64 {
+            return Err(format!(
+                "Price from log price {} is out of range",
+                log_price
+            ));
+        }
+
+        let mut exponent = MAX_SCALE_EXP;
+        while exponent > 0 && price * 10f64.powi(exponent) > MAX_RATE as f64 {
+            exponent -= 1;
+        }
+        let output_rate = (price * 10f64.powi(exponent)).round() as u128;
+        if output_rate == 0 {
+            return Err(format!("Price from log price {} is too small", log_price));
+        }
+
+        let rate = Self::reduce_rate((10u128.pow(exponent as u32), output_rate));
+        Self::new(token_pair, rate, decimals).map_err(String::from)
+    }
+
+    /// 由年化收益率创建价格对，汇率（输出/输入）为 `1 + apr * duration / year`
+    ///
+    /// `apr_bps` 以基点表示，一年按 365 天计；汇率以化简后的精确分数表示（单利）。
+    pub fn from_apr(
+        token_pair: (String, String),
+        apr_bps: u32,
+        duration_secs: u64,
+        decimals: (u8, u8),
+    ) -> Result<Self, String> {
+        const SECONDS_PER_YEAR: u128 = 365 * 24 * 60 * 60;
+
+        let denominator = BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR;
+        let accrued = apr_bps as u128 * duration_secs as u128;
+        let rate = Self::reduce_rate((denominator, denominator + accrued));
+        Self::new(token_pair, rate, decimals).map_err(String::from)
+    }
+
+    /// 由 Q64.64 定点数汇率（输出/输入）创建价格对
+    ///
+    /// 高 64 位为整数部分、低 64 位为小数部分，汇率为 `q / 2^64` 化简后的精确分数。
+    pub fn from_q64_64(
+        token_pair: (String, String),
+        q: u128,
+        decimals: (u8, u8),
+    ) -> Result<Self, String> {
+        if q == 0 {
+            return Err("Q64.64 rate must be greater than 0".to_string());
+        }
+        let rate = Self::reduce_rate((1u128 << 64, q));
+        Self::new(token_pair, rate, decimals).map_err(String::from)
+    }
+
+    /// 将汇率（输出/输入）转换为 Q64.64 定点数，小数部分向下截断
+    pub fn to_q64_64(&self) -> Result<u128, String> {
+        Self::validate_rate(self.rate)?;
+        math::mul_div_floor(self.rate.1, 1u128 << 64, self.rate.0)
+            .ok_or("Rate exceeds Q64.64 range".to_string())
+    }
+
+    /// 由预言机报价创建价格对，人类可读汇率为 `answer / 10^answer_decimals`
+    pub fn from_oracle_answer(
+        token_pair: (String, String),
+        answer: u128,
+        answer_decimals: u8,
+        decimals: (u8, u8),
+    ) -> Result<Self, String> {
+        if answer == 0 {
+            return Err("Oracle answer must be greater than 0".to_string());
+        }
+        Self::validate_decimals(decimals)?;
+
+        // rate.1 / rate.0 = answer * 10^decimals.1 / 10^(answer_decimals + decimals.0)
+        let exponent = answer_decimals as i32 + decimals.0 as i32 - decimals.1 as i32;
+        let overflow = || "Oracle rate overflow".to_string();
+        let power = 10u128
+            .checked_pow(exponent.unsigned_abs())
+            .ok_or_else(overflow)?;
+        let rate = if exponent >= 0 {
+            (power, answer)
+        } else {
+            (1, answer.checked_mul(power).ok_or_else(overflow)?)
+        };
+        Self::new(token_pair, Self::reduce_rate(rate), decimals).map_err(String::from)
+    }
+
+    /// 将人类可读汇率表示为 `answer_decimals` 位小数的预言机报价（四舍五入）
+    pub fn to_oracle_answer(&self, answer_decimals: u8) -> Result<u128, String> {
+        Self::validate_rate(self.rate)?;
+        Self::validate_decimals(self.decimals)?;
+
+        // answer = rate.1 * 10^(answer_decimals + decimals.0 - decimals.1) / rate.0
+        let (input_rate, output_rate) = self.rate;
+        let exponent = answer_decimals as i32 + self.decimals.0 as i32 - self.decimals.1 as i32;
+        let overflow = || "Oracle answer overflow".to_string();
+        let power = 10u128.checked_pow(exponent.unsigned_abs());
+        let (numerator, multiplier, divisor) = match power {
+            Some(power) if exponent >= 0 => (output_rate, power, input_rate),
+            Some(power) => match input_rate.checked_mul(power) {
+                Some(divisor) => (output_rate, 1, divisor),
+                // 除数超出 u128 时报价必然小于 0.5
+                None => return Ok(0),
+            },
+            None if exponent >= 0 => return Err(overflow()),
+            None => return Ok(0),
+        };
+
+        let (quotient, remainder) =
+            math::mul_div_rem(numerator, multiplier, divisor).ok_or_else(overflow)?;
+        RoundingMode::HalfUp
+            .round(quotient, remainder, divisor)
+            .ok_or_else(overflow)
+    }
+
+    /// 将人类可读汇率四舍五入到 `answer_decimals` 位小数，模拟预言机报价的精度
+    ///
+    /// 结果与 `from_oracle_answer(to_oracle_answer(answer_decimals))` 相同；
+    /// 汇率在该精度下舍入为 0 时返回错误。
+    pub fn quantize_to_oracle(&self, answer_decimals: u8) -> Result<PairRate, String> {
+        let answer = self.to_oracle_answer(answer_decimals)?;
+        Self::from_oracle_answer(
+            self.token_pair.clone(),
+            answer,
+            answer_decimals,
+            self.decimals,
+        )
+    }
+
+    /// 恒定乘积池（x·y=k）的瞬时边际价格，以最简 `(输入比率, 输出比率)` 返回
+    ///
+    /// 边际价格为 `reserve_out / reserve_in`，可直接作为 `PairRate` 的汇率
+    /// （分量超出 `MAX_RATE` 时 `new` 会拒绝）。
+    pub fn marginal_price_cpmm(
+        reserve_in: u128,
+        reserve_out: u128,
+    ) -> Result<(u128, u128), String> {
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err("Reserves must be greater than 0".to_string());
+        }
+        Ok(Self::reduce_rate((reserve_in, reserve_out)))
+    }
+
+    /// 创建同一代币原生形式与包装形式之间 1:1 兑换的价格对
+    ///
+    /// 两侧使用相同的代币符号，仅精度不同（输入为原生精度，输出为包装精度）。
+    /// 精度不同时 `is_identity` 返回 `false`，换算只做精度调整。
+    pub fn wrap(token: String, unwrapped_decimals: u8, wrapped_decimals: u8) -> PairRate {
+        PairRate {
+            token_pair: (token.clone(), token),
+            rate: (1, 1),
+            decimals: (unwrapped_decimals, wrapped_decimals),
+        }
+    }
+
+    /// 是否为恒等换算：同一代币、1:1 汇率且两侧精度相同
+    pub fn is_identity(&self) -> bool {
+        self.token_pair.0 == self.token_pair.1
+            && self.rate.0 == self.rate.1
+            && self.decimals.0 == self.decimals.1
+    }
+
     /// 根据价格和输入代币数量计算输出代币数量
-    pub fn calculate_output_amount(price: &PairRate, input_amount: u128) -> Result<u128, String> {
+    pub fn calculate_output_amount(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<u128, PairRateError> {
+        Self::calculate_output_amount_with_rounding(price, input_amount, RoundingMode::Truncate)
+    }
+
+    /// 按指定舍入方式计算输出代币数量
+    ///
+    /// 汇率乘除与精度降低时的除法两步都按 `mode` 舍入。
+    pub fn calculate_output_amount_with_rounding(
+        price: &PairRate,
+        input_amount: u128,
+        mode: RoundingMode,
+    ) -> Result<u128, PairRateError> {
         if input_amount == 0 {
-            return Err("Input amount must be greater than 0".to_string());
+            return Err(PairRateError::ZeroInputAmount);
         }
 
         Self::validate_rate(price.rate)?;
         Self::validate_decimals(price.decimals)?;
 
-        let (input_rate, output_rate) = price.rate;
+        Self::validated_output_amount(price.rate, price.decimals, input_amount, mode)
+    }
 
-        // 预检查：计算是否可能溢出
-        if input_amount > u128::MAX / output_rate {
-            return Err("Input amount too large, would cause overflow".to_string());
+    /// 批量计算同一交易对下多个输入数量的输出数量
+    ///
+    /// 汇率与精度只校验一次；遇到第一个出错的输入即返回，错误为携带其下标的
+    /// `PairRateError::AtIndex`。
+    pub fn calculate_output_amounts(
+        price: &PairRate,
+        inputs: &[u128],
+    ) -> Result<Vec<u128>, PairRateError> {
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
+
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(index, &input_amount)| {
+                Self::validated_output_amount(
+                    price.rate,
+                    price.decimals,
+                    input_amount,
+                    RoundingMode::Truncate,
+                )
+                .map_err(|error| PairRateError::AtIndex {
+                    index,
+                    error: Box::new(error),
+                })
+            })
+            .collect()
+    }
+
+    /// 与 `calculate_output_amounts` 相同，但逐个返回结果，单个输入出错不影响其余输入
+    ///
+    /// 汇率或精度无效时，每个元素都返回该校验错误。
+    pub fn calculate_output_amounts_partial(
+        price: &PairRate,
+        inputs: &[u128],
+    ) -> Vec<Result<u128, PairRateError>> {
+        let validation =
+            Self::validate_rate(price.rate).and_then(|_| Self::validate_decimals(price.decimals));
+
+        inputs
+            .iter()
+            .map(|&input_amount| match &validation {
+                Ok(()) => Self::validated_output_amount(
+                    price.rate,
+                    price.decimals,
+                    input_amount,
+                    RoundingMode::Truncate,
+                ),
+                Err(error) => Err(error.clone()),
+            })
+            .collect()
+    }
+
+    /// 在汇率与精度已校验的前提下按 `mode` 计算输出代币数量
+    fn validated_output_amount(
+        rate: (u128, u128),
+        decimals: (u8, u8),
+        input_amount: u128,
+        mode: RoundingMode,
+    ) -> Result<u128, PairRateError> {
+        if input_amount == 0 {
+            return Err(PairRateError::ZeroInputAmount);
+        }
+
+        let (input_rate, output_rate) = rate;
+
+        // 预检查：基础结果是否超出 u128（乘积本身允许超出，由乘除运算按 256 位处理）
+        if math::mul_div_floor(input_amount, output_rate, input_rate).is_none() {
+            return Err(PairRateError::overflow(
+                OverflowStage::MultiplyDivide,
+                (input_amount, output_rate),
+            ));
         }
 
         // 基础计算：input_amount * output_rate / input_rate
-        let base_output = Self::safe_multiply_divide(input_amount, output_rate, input_rate)?;
+        let base_output =
+            Self::rounded_multiply_divide(input_amount, output_rate, input_rate, mode)?;
 
         // 精度调整：将结果从input_decimals调整到output_decimals
         let adjusted_output =
-            Self::adjust_decimals(base_output, price.decimals.0, price.decimals.1)?;
+            Self::adjust_decimals_with_rounding(base_output, decimals.0, decimals.1, mode)?;
 
         if adjusted_output == 0 {
-            return Err("Calculated output amount is zero, increase input amount".to_string());
+            return Err(PairRateError::ZeroCalculatedOutput);
         }
 
         Ok(adjusted_output)
     }
 
-    /// 根据价格和输出代币数量计算需要的输入代币数量
-    pub fn calculate_input_amount(price: &PairRate, output_amount: u128) -> Result<u128, String> {
-        if output_amount == 0 {
-            return Err("Output amount must be greater than 0".to_string());
-        }
-
-        Self::validate_rate(price.rate)?;
-        Self::validate_decimals(price.decimals)?;
+    /// 计算输出代币数量，并附带输出代币的符号与精度
+    pub fn calculate_output_meta(&self, input_amount: u128) -> Result<AmountWithMeta, String> {
+        let value = Self::calculate_output_amount(self, input_amount)?;
+        Ok(AmountWithMeta {
+            value,
+            token: self.token_pair.1.clone(),
+            decimals: self.decimals.1,
+        })
+    }
 
-        let (input_rate, output_rate) = price.rate;
+    /// 一次计算完整报价：输出数量、实际成交比率与截断造成的输入粉尘
+    ///
+    /// 各字段由同一次换算得到，保证彼此一致。
+    pub fn quote(price: &PairRate, input_amount: u128) -> Result<Quote, PairRateError> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let min_input = Self::min_input_for_output(price, output_amount)?;
+        let divisor = input_amount.gcd(&output_amount);
 
-        // 预检查：计算是否可能溢出
-        if output_amount > u128::MAX / input_rate {
-            return Err("Output amount too large, would cause overflow".to_string());
-        }
+        Ok(Quote {
+            input_amount,
+            output_amount,
+            effective_rate: (input_amount / divisor, output_amount / divisor),
+            dust: input_amount - min_input,
+        })
+    }
 
-        // 基础计算：output_amount * input_rate / output_rate
-        let base_input = Self::safe_multiply_divide(output_amount, input_rate, output_rate)?;
+    /// 计算输出代币数量，乘积的位数超过 `max_bits` 时在分配大整数之前拒绝
+    ///
+    /// 乘积位数按 `input_amount` 与输出比率的有效位数之和估计（上界），
+    /// 用于在不可信输入下限制内存与计算开销。
+    pub fn calculate_output_amount_bounded(
+        price: &PairRate,
+        input_amount: u128,
+        max_bits: u32,
+    ) -> Result<u128, PairRateError> {
+        Self::check_bit_length(input_amount, price.rate.1, max_bits)?;
+        Self::calculate_output_amount(price, input_amount)
+    }
 
-        // 精度调整：将结果从output_decimals调整到input_decimals
-        let adjusted_input = Self::adjust_decimals(base_input, price.decimals.1, price.decimals.0)?;
+    /// 检查 `a * b` 的位数上界不超过 `max_bits`
+    fn check_bit_length(a: u128, b: u128, max_bits: u32) -> Result<(), PairRateError> {
+        let bits = (128 - a.leading_zeros()) + (128 - b.leading_zeros());
+        if bits > max_bits {
+            return Err(PairRateError::OperandTooLarge { bits, max_bits });
+        }
+        Ok(())
+    }
 
-        if adjusted_input == 0 {
-            return Err("Calculated input amount is zero, increase output amount".to_string());
+    /// 使用交易对的汇率、但以 `decimals_override` 代替其精度计算输出代币数量
+    ///
+    /// 仅对本次调用生效，便于试验精度变化而无需构建临时交易对。
+    pub fn calculate_output_amount_with_decimals(
+        price: &PairRate,
+        input_amount: u128,
+        decimals_override: (u8, u8),
+    ) -> Result<u128, String> {
+        if input_amount == 0 {
+            return Err(PairRateError::ZeroInputAmount.into());
         }
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(decimals_override)?;
 
-        Ok(adjusted_input)
+        Self::validated_output_amount(
+            price.rate,
+            decimals_override,
+            input_amount,
+            RoundingMode::Truncate,
+        )
+        .map_err(String::from)
     }
 
-    /// 精度调整函数
-    fn adjust_decimals(amount: u128, from_decimals: u8, to_decimals: u8) -> Result<u128, String> {
-        // 验证精度范围
-        if from_decimals > MAX_DECIMALS || to_decimals > MAX_DECIMALS {
-            return Err(format!(
-                "Decimals must be less than or equal to {}",
-                MAX_DECIMALS
-            ));
-        }
+    /// 计算输出代币数量，同时返回最后一步除法的余数 `(输出数量, 余数)`
+    ///
+    /// - 输出精度低于输入精度时，最后一步是除以 `10^(decimals.0 - decimals.1)`，
+    ///   余数为汇率乘除结果对该除数取模，即 `余数 / 10^(decimals.0 - decimals.1)` 个输出最小单位；
+    /// - 否则最后一步除法是汇率除法，余数为 `input_amount * rate.1` 对 `rate.0` 取模，
+    ///   即 `余数 * 10^(decimals.1 - decimals.0) / rate.0` 个输出最小单位。
+    ///
+    /// 只反映最后一步除法；需要两步截断合计的损失时使用 `calculate_output_amount_with_loss`。
+    pub fn calculate_output_amount_with_remainder(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<(u128, u128), PairRateError> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
 
-        // 检查精度差异
-        let decimal_diff = if from_decimals > to_decimals {
-            from_decimals - to_decimals
+        let (input_rate, output_rate) = price.rate;
+        let (base_output, base_remainder) =
+            math::mul_div_rem(input_amount, output_rate, input_rate).ok_or(
+                PairRateError::overflow(OverflowStage::MultiplyDivide, (input_amount, output_rate)),
+            )?;
+
+        let (input_decimals, output_decimals) = price.decimals;
+        let remainder = if output_decimals < input_decimals {
+            base_output % 10u128.pow((input_decimals - output_decimals) as u32)
         } else {
-            to_decimals - from_decimals
+            base_remainder
         };
 
-        if decimal_diff > MAX_DECIMAL_DIFF {
-            return Err(format!(
-                "Decimal difference {} exceeds maximum allowed {}",
-                decimal_diff, MAX_DECIMAL_DIFF
-            ));
-        }
+        Ok((output_amount, remainder))
+    }
 
-        if from_decimals == to_decimals {
-            return Ok(amount);
+    /// 计算输出代币数量，同时返回截断损失 `(输出数量, (损失整数部分, 损失小数部分))`
+    ///
+    /// 损失为精确输出与截断输出之差，单位始终是输出代币最小单位；小数部分为乘以 2^64 后的整数值。
+    /// 汇率除法和精度调整两步的截断都计入损失。
+    pub fn calculate_output_amount_with_loss(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<(u128, (u128, u128)), PairRateError> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+
+        let (floor_exact, fraction) =
+            Self::exact_output_parts(price, input_amount).map_err(|_| {
+                PairRateError::overflow(OverflowStage::MultiplyDivide, (input_amount, price.rate.1))
+            })?;
+
+        Ok((output_amount, (floor_exact - output_amount, fraction)))
+    }
+
+    /// 只使用 `checked_mul`/`checked_div` 计算输出代币数量，不依赖 BigUint
+    ///
+    /// 乘法溢出时按部分积拆分计算，仅在结果确实超出 u128 时报错，
+    /// 在可表示范围内与 `calculate_output_amount` 结果一致。
+    pub fn calculate_output_amount_no_bigint(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<u128, PairRateError> {
+        Self::calculate_output_amount_via(price, input_amount, Self::checked_multiply_divide)
+    }
+
+    /// 使用给定的乘除实现计算输出代币数量
+    fn calculate_output_amount_via(
+        price: &PairRate,
+        input_amount: u128,
+        multiply_divide: fn(u128, u128, u128) -> Result<u128, PairRateError>,
+    ) -> Result<u128, PairRateError> {
+        if input_amount == 0 {
+            return Err(PairRateError::ZeroInputAmount);
         }
 
-        if from_decimals > to_decimals {
-            // 精度降低，需要除法
-            let decimal_diff = from_decimals - to_decimals;
-            let divisor = 10u128
-                .checked_pow(decimal_diff as u32)
-                .ok_or("Decimal divisor overflow")?;
-            Ok(amount / divisor)
-        } else {
-            // 精度提高，需要乘法
-            let decimal_diff = to_decimals - from_decimals;
-            let multiplier = 10u128
-                .checked_pow(decimal_diff as u32)
-                .ok_or("Decimal multiplier overflow")?;
-            amount
-                .checked_mul(multiplier)
-                .ok_or("Decimal adjustment caused overflow".to_string())
+        let adjusted_output = Self::truncated_output_amount(price, input_amount, multiply_divide)?;
+
+        if adjusted_output == 0 {
+            return Err(PairRateError::ZeroCalculatedOutput);
         }
+
+        Ok(adjusted_output)
     }
 
-    /// 安全的乘除运算，防止溢出
-    fn safe_multiply_divide(amount: u128, multiplier: u128, divisor: u128) -> Result<u128, String> {
-        if divisor == 0 {
-            return Err("Division by zero".to_string());
-        }
+    /// 计算截断后的输出代币数量，不对零输入或零输出报错
+    fn truncated_output_amount(
+        price: &PairRate,
+        input_amount: u128,
+        multiply_divide: fn(u128, u128, u128) -> Result<u128, PairRateError>,
+    ) -> Result<u128, PairRateError> {
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
 
-        // 预检查：验证输入值范围
-        if amount > MAX_RATE || multiplier > MAX_RATE {
-            return Err("Input values too large for safe calculation".to_string());
+        let (input_rate, output_rate) = price.rate;
+
+        // 预检查：基础结果是否超出 u128（乘积本身允许超出，由乘除运算按 256 位处理）
+        if math::mul_div_floor(input_amount, output_rate, input_rate).is_none() {
+            return Err(PairRateError::overflow(
+                OverflowStage::MultiplyDivide,
+                (input_amount, output_rate),
+            ));
         }
 
-        let amount = BigUint::from(amount);
-        let multiplier = BigUint::from(multiplier);
-        let divisor = BigUint::from(divisor);
+        // 基础计算：input_amount * output_rate / input_rate
+        let base_output = multiply_divide(input_amount, output_rate, input_rate)?;
 
-        let result = amount * multiplier / divisor;
+        // 精度调整：将结果从input_decimals调整到output_decimals
+        Self::adjust_decimals(base_output, price.decimals.0, price.decimals.1)
+    }
 
-        result.to_u128().ok_or("Result exceeds u128".to_string())
+    /// 计算输出代币数量，交易对汇率低于底价 `floor_rate` 时拒绝换算
+    ///
+    /// `floor_rate` 与 `price.rate` 含义相同（`(输入比率, 输出比率)`，沿用交易对精度）。
+    pub fn calculate_output_amount_with_floor(
+        price: &PairRate,
+        input_amount: u128,
+        floor_rate: (u128, u128),
+    ) -> Result<u128, String> {
+        Self::calculate_output_amount_floor_rate(price, input_amount, floor_rate, false)
     }
 
-    /// 获取价格率，返回比率和精度
-    pub fn get_price_rate(&self) -> ((u128, u128), (u8, u8)) {
-        (self.rate, self.decimals)
+    /// 与 `calculate_output_amount_with_floor` 相同，但汇率低于底价时按底价换算
+    pub fn calculate_output_amount_clamped_to_floor(
+        price: &PairRate,
+        input_amount: u128,
+        floor_rate: (u128, u128),
+    ) -> Result<u128, String> {
+        Self::calculate_output_amount_floor_rate(price, input_amount, floor_rate, true)
     }
 
-    /// 获取人类可读的价格率（注意：可能损失精度）
-    pub fn get_human_readable_rate(&self) -> f64 {
-        let (input_rate, output_rate) = self.rate;
-        let (input_decimals, output_decimals) = self.decimals;
+    /// 按底价检查汇率，`clamp` 决定低于底价时报错还是按底价换算
+    fn calculate_output_amount_floor_rate(
+        price: &PairRate,
+        input_amount: u128,
+        floor_rate: (u128, u128),
+        clamp: bool,
+    ) -> Result<u128, String> {
+        Self::validate_rate(floor_rate)?;
+        Self::validate_rate(price.rate)?;
 
-        let decimal_adjustment = if input_decimals > output_decimals {
-            10f64.powi((input_decimals - output_decimals) as i32)
-        } else {
-            1f64 / 10f64.powi((output_decimals - input_decimals) as i32)
+        // price.rate.1 / price.rate.0 < floor_rate.1 / floor_rate.0
+        let below_floor = math::widening_mul(price.rate.1, floor_rate.0)
+            < math::widening_mul(floor_rate.1, price.rate.0);
+        if !below_floor {
+            return Self::calculate_output_amount(price, input_amount).map_err(String::from);
+        }
+        if !clamp {
+            return Err(format!(
+                "Rate {:?} is below floor rate {:?}",
+                price.rate, floor_rate
+            ));
+        }
+
+        let floor_price = PairRate {
+            rate: floor_rate,
+            ..price.clone()
         };
+        Self::calculate_output_amount(&floor_price, input_amount).map_err(String::from)
+    }
 
-        (output_rate as f64 / input_rate as f64) * decimal_adjustment
+    /// 模拟一连串同方向的换算，返回每笔交易后的输出代币余额
+    ///
+    /// 模型：`initial` 为输出代币的初始余额，`trades` 中每一项都是一笔输入代币
+    /// 数量，按当前汇率换算为输出代币后累加到余额中。任一笔换算失败或余额溢出时
+    /// 返回错误。
+    pub fn simulate(&self, initial: u128, trades: &[u128]) -> Result<Vec<u128>, String> {
+        let mut balance = initial;
+        trades
+            .iter()
+            .map(|&input_amount| {
+                let output_amount = Self::calculate_output_amount(self, input_amount)?;
+                balance = balance
+                    .checked_add(output_amount)
+                    .ok_or("Simulated balance overflow".to_string())?;
+                Ok(balance)
+            })
+            .collect()
     }
 
-    /// 验证价格是否有效
-    pub fn is_valid(&self) -> bool {
-        Self::validate_rate(self.rate).is_ok() && Self::validate_decimals(self.decimals).is_ok()
+    /// 调用时从 `rate_source` 读取最新汇率，并沿用当前交易对的精度计算输出
+    pub fn calculate_output_dynamic<F: Fn() -> (u128, u128)>(
+        &self,
+        input_amount: u128,
+        rate_source: F,
+    ) -> Result<u128, String> {
+        let rate = rate_source();
+        Self::validate_rate(rate)?;
+
+        let price = PairRate {
+            rate,
+            ..self.clone()
+        };
+        Self::calculate_output_amount(&price, input_amount).map_err(String::from)
     }
-}
 
-impl Default for PairRate {
-    fn default() -> Self {
-        PairRate {
-            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            rate: (1, 1),       // 默认1:1价格
-            decimals: (18, 18), // 默认18位精度
+    /// 按交易规模分档的滑点计算输出代币数量
+    ///
+    /// `curve` 为按输入阈值严格递增排列的 `(输入阈值, 滑点基点)`，选用阈值不超过
+    /// `input_amount` 的最后一档；输入低于第一档阈值时不计滑点。滑点按
+    /// `(10_000 - bps) / 10_000` 下调汇率，等价于对输出按该比例向下取整。
+    pub fn calculate_output_amount_sized_slippage(
+        price: &PairRate,
+        input_amount: u128,
+        curve: &[(u128, u16)],
+    ) -> Result<u128, String> {
+        if curve.windows(2).any(|w| w[0].0 >= w[1].0) {
+            return Err("Slippage curve thresholds must be strictly increasing".to_string());
+        }
+        for &(_, bps) in curve {
+            Self::validate_slippage(bps)?;
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let slippage_bps = curve
+            .iter()
+            .take_while(|&&(threshold, _)| threshold <= input_amount)
+            .last()
+            .map_or(0, |&(_, bps)| bps);
 
-    #[test]
-    fn test_calculate_output_amount() {
-        let price = PairRate::new(
-            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (10, 19), // 1 input_token = 1.9 output_token
-            (24, 24),
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let output_amount = math::mul_div_floor(
+            output_amount,
+            (BPS_DENOMINATOR - slippage_bps) as u128,
+            BPS_DENOMINATOR as u128,
         )
-        .unwrap();
+        .ok_or("Slippage calculation overflow".to_string())?;
 
-        // 输入1个token（24位精度）
-        let input_amount = 200_000_000_000_000_000_000_000_000u128; // 1 token with 24 decimals
-        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
-        println!("Output amount: {}", output);
+        if output_amount == 0 {
+            return Err("Calculated output amount is zero, increase input amount".to_string());
+        }
+        Ok(output_amount)
     }
 
-    #[test]
-    fn test_calculate_output_amount_r() {
-        let price = PairRate::new(
-            ("TOKEN_B".to_string(), "TOKEN_A".to_string()),
-            (19, 10),
-            (24, 24),
-        )
-        .unwrap();
+    /// 计算扣除滑点与手续费后的净输出
+    ///
+    /// 先按汇率换算，再按 `(10_000 - slippage_bps) / 10_000` 下调，最后扣除
+    /// `fee_bps` 基点手续费，每一步都向下取整。
+    pub fn calculate_output_full(
+        price: &PairRate,
+        input_amount: u128,
+        fee_bps: u16,
+        slippage_bps: u16,
+    ) -> Result<u128, String> {
+        Self::validate_fee(fee_bps, MAX_FEE_BPS)?;
+        Self::validate_slippage(slippage_bps)?;
 
-        // 输入1个token（24位精度）
-        let input_amount = 200_000_000_000_000_000_000_000_000u128; // 1 token with 24 decimals
-        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
-        println!("Output amount: {}", output);
+        let deduct = |amount: u128, bps: u16| {
+            math::mul_div_floor(
+                amount,
+                (BPS_DENOMINATOR - bps) as u128,
+                BPS_DENOMINATOR as u128,
+            )
+            .ok_or("Fee calculation overflow".to_string())
+        };
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let net_output = deduct(deduct(output_amount, slippage_bps)?, fee_bps)?;
+
+        if net_output == 0 {
+            return Err("Calculated output amount is zero, increase input amount".to_string());
+        }
+        Ok(net_output)
+    }
+
+    /// 计算最坏情况下净得 `desired_net_output` 所需的最小输入
+    ///
+    /// 依次为手续费与滑点向上取整地反推所需的毛输出，再按截断规则反推最小输入，
+    /// 使 `calculate_output_full` 的结果不低于 `desired_net_output`。
+    pub fn calculate_input_full(
+        price: &PairRate,
+        desired_net_output: u128,
+        fee_bps: u16,
+        slippage_bps: u16,
+    ) -> Result<u128, String> {
+        if desired_net_output == 0 {
+            return Err("Output amount must be greater than 0".to_string());
+        }
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
+        Self::validate_fee(fee_bps, MAX_FEE_BPS)?;
+        Self::validate_slippage(slippage_bps)?;
+        if fee_bps == BPS_DENOMINATOR || slippage_bps == BPS_DENOMINATOR {
+            return Err("Cannot deliver output with a 100% deduction".to_string());
+        }
+
+        let gross_up = |amount: u128, bps: u16| {
+            math::mul_div_ceil(
+                amount,
+                BPS_DENOMINATOR as u128,
+                (BPS_DENOMINATOR - bps) as u128,
+            )
+            .ok_or("Gross-up calculation overflow".to_string())
+        };
+        let gross_output = gross_up(gross_up(desired_net_output, fee_bps)?, slippage_bps)?;
+        let input_amount = Self::min_input_for_output(price, gross_output)?;
+
+        // 确认该输入可以正常换算（不触发溢出检查）
+        Self::calculate_output_full(price, input_amount, fee_bps, slippage_bps)?;
+        Ok(input_amount)
+    }
+
+    /// 将一组输入的换算结果导出为 CSV
+    ///
+    /// 表头为 `input,output,error`。单个输入换算失败时该行 `output` 留空，
+    /// 错误信息写入 `error` 列而不是中止导出；交易对本身无效时返回错误。
+    pub fn to_csv(&self, inputs: &[u128]) -> Result<String, String> {
+        Self::validate_rate(self.rate)?;
+        Self::validate_decimals(self.decimals)?;
+
+        let mut csv = String::from("input,output,error\n");
+        for &input_amount in inputs {
+            match Self::calculate_output_amount(self, input_amount) {
+                Ok(output_amount) => {
+                    csv.push_str(&format!("{},{},\n", input_amount, output_amount));
+                }
+                Err(err) => {
+                    csv.push_str(&format!(
+                        "{},,\"{}\"\n",
+                        input_amount,
+                        err.to_string().replace('"', "\"\"")
+                    ));
+                }
+            }
+        }
+        Ok(csv)
+    }
+
+    /// 计算输出代币数量，并把报价记录追加到调用方提供的审计日志中
+    ///
+    /// 只有换算成功时才会写入日志。
+    pub fn calculate_output_logged(
+        &self,
+        input_amount: u128,
+        log: &mut Vec<QuoteRecord>,
+    ) -> Result<u128, String> {
+        let output_amount = Self::calculate_output_amount(self, input_amount)?;
+        log.push(QuoteRecord {
+            pair: self.clone(),
+            input_amount,
+            output_amount,
+            timestamp: 0,
+        });
+        Ok(output_amount)
+    }
+
+    /// 计算输出代币数量，按 `policy` 处理换算结果为零的情况
+    pub fn calculate_output_amount_zero_policy(
+        price: &PairRate,
+        input_amount: u128,
+        policy: ZeroPolicy,
+    ) -> Result<u128, String> {
+        if input_amount == 0 {
+            return Err("Input amount must be greater than 0".to_string());
+        }
+
+        let output_amount =
+            Self::truncated_output_amount(price, input_amount, Self::safe_multiply_divide)?;
+        if output_amount > 0 {
+            return Ok(output_amount);
+        }
+
+        match policy {
+            ZeroPolicy::Error => {
+                Err("Calculated output amount is zero, increase input amount".to_string())
+            }
+            ZeroPolicy::ReturnZero => Ok(0),
+            ZeroPolicy::MinOne => Ok(1),
+        }
+    }
+
+    /// 计算输出代币数量，以 `U256` 返回完整结果而不收窄到 u128
+    ///
+    /// 中间计算全部使用 256 位整数，只有结果超出 `U256` 时才报错。
+    #[cfg(feature = "u256")]
+    pub fn calculate_output_amount_u256(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<U256, String> {
+        if input_amount == 0 {
+            return Err("Input amount must be greater than 0".to_string());
+        }
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
+
+        let (input_rate, output_rate) = price.rate;
+        let (input_decimals, output_decimals) = price.decimals;
+        let decimal_diff = input_decimals.abs_diff(output_decimals);
+        if decimal_diff > MAX_DECIMAL_DIFF {
+            return Err(PairRateError::DecimalDiffTooLarge {
+                diff: decimal_diff,
+                max: MAX_DECIMAL_DIFF,
+            }
+            .into());
+        }
+
+        let base_output =
+            U256::from(input_amount) * U256::from(output_rate) / U256::from(input_rate);
+        let scale = U256::from(10u8).pow(U256::from(decimal_diff));
+        let output_amount = if output_decimals >= input_decimals {
+            base_output.checked_mul(scale).ok_or_else(|| {
+                String::from(PairRateError::Overflow {
+                    stage: OverflowStage::DecimalScaleUp,
+                    operand_bits: (base_output.bits() as u32, scale.bits() as u32),
+                })
+            })?
+        } else {
+            base_output / scale
+        };
+
+        if output_amount.is_zero() {
+            return Err("Calculated output amount is zero, increase input amount".to_string());
+        }
+        Ok(output_amount)
+    }
+
+    /// 计算输出代币数量，超出容量时报告最大可用输入及超出部分
+    ///
+    /// 输入未超出容量但因其他原因（输入为零、输出为零、价格无效）失败时，
+    /// 返回 `excess` 为 0 的 `CapacityError`。
+    pub fn calculate_output_amount_capacity(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<u128, CapacityError> {
+        let max_input = Self::max_input_capacity(price);
+        if input_amount > max_input {
+            return Err(CapacityError {
+                max_input,
+                excess: input_amount - max_input,
+            });
+        }
+
+        Self::calculate_output_amount(price, input_amount).map_err(|_| CapacityError {
+            max_input,
+            excess: 0,
+        })
+    }
+
+    /// 计算输出代币数量，输出超过池子储备 `output_reserve` 时拒绝换算
+    ///
+    /// 错误信息中给出输出不超过储备的最大输入数量。
+    pub fn calculate_output_within_reserve(
+        price: &PairRate,
+        input_amount: u128,
+        output_reserve: u128,
+    ) -> Result<u128, String> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        if output_amount <= output_reserve {
+            return Ok(output_amount);
+        }
+
+        // 换得 output_reserve + 1 的最小输入减一，即为不超过储备的最大输入
+        let max_fillable_input = Self::min_input_for_output(price, output_reserve + 1)? - 1;
+        Err(format!(
+            "Output amount {} exceeds reserve {}, max fillable input {}",
+            output_amount, output_reserve, max_fillable_input
+        ))
+    }
+
+    /// 计算铸造出恰好 `output_cap` 个输出代币所需的输入数量
+    ///
+    /// 通过 `calculate_input_amount` 反算；若 `output_cap` 超过交易对在最大可用输入下
+    /// 能产生的输出，返回错误并给出该上限。
+    pub fn max_input_for_output_cap(price: &PairRate, output_cap: u128) -> Result<u128, String> {
+        let max_input = Self::max_input_capacity(price);
+        let max_output = Self::calculate_output_amount(price, max_input)?;
+        if output_cap > max_output {
+            return Err(format!(
+                "Output cap {} exceeds maximum producible output {}",
+                output_cap, max_output
+            ));
+        }
+
+        Self::calculate_input_amount(price, output_cap).map_err(String::from)
+    }
+
+    /// 计算不会触发溢出检查的最大输入数量，价格无效时为 0
+    fn max_input_capacity(price: &PairRate) -> u128 {
+        if Self::validate_rate(price.rate).is_err()
+            || Self::validate_decimals(price.decimals).is_err()
+        {
+            return 0;
+        }
+
+        let (input_rate, output_rate) = price.rate;
+        let (input_decimals, output_decimals) = price.decimals;
+        let decimal_diff = input_decimals.abs_diff(output_decimals);
+        if decimal_diff > MAX_DECIMAL_DIFF {
+            return 0;
+        }
+
+        // 基础结果不超出 u128，且满足 safe_multiply_divide 的范围检查；
+        // 最大的 x 使 x * output_rate / input_rate <= u128::MAX 为 q 或 q + 1
+        let mut max_input = match math::mul_div_floor(u128::MAX, input_rate, output_rate) {
+            Some(q) => q
+                .checked_add(1)
+                .filter(|&next| math::mul_div_floor(next, output_rate, input_rate).is_some())
+                .unwrap_or(q),
+            None => u128::MAX,
+        }
+        .min(MAX_RATE);
+
+        // 精度提高时，基础结果乘以 10^diff 不能溢出
+        if output_decimals > input_decimals {
+            let max_base = u128::MAX / 10u128.pow(decimal_diff as u32);
+            // 最大的 x 使 x * output_rate / input_rate <= max_base
+            if let Some(limit) = math::mul_div_ceil(max_base + 1, input_rate, output_rate) {
+                max_input = max_input.min(limit - 1);
+            }
+        }
+
+        max_input
+    }
+
+    /// 按一组价格档位分别计算输出数量，沿用当前交易对的精度
+    ///
+    /// 每个档位是一个 `(输入比率, 输出比率)`，结果顺序与档位顺序一致，
+    /// 任一档位计算失败时返回该错误。
+    pub fn ladder_outputs(
+        &self,
+        input_amount: u128,
+        levels: &[(u128, u128)],
+    ) -> Result<Vec<u128>, String> {
+        let mut level_price = self.clone();
+        levels
+            .iter()
+            .map(|&rate| {
+                level_price.rate = rate;
+                Self::calculate_output_amount(&level_price, input_amount).map_err(String::from)
+            })
+            .collect()
+    }
+
+    /// 计算输入从 0 均匀增长到 `max_input` 时各步的累计输出
+    ///
+    /// 返回 `steps + 1` 个值，第 i 个对应输入 `max_input * i / steps`。
+    /// 模型是线性的，但截断会让输出呈阶梯状增长；不足一个输出最小单位的
+    /// 结果记为 0 而不是报错。
+    pub fn cumulative_output(&self, max_input: u128, steps: u32) -> Result<Vec<u128>, String> {
+        if steps == 0 {
+            return Err("Steps must be greater than 0".to_string());
+        }
+
+        (0..=steps)
+            .map(|step| {
+                let input_amount = math::mul_div_floor(max_input, step as u128, steps as u128)
+                    .ok_or("Step input overflow".to_string())?;
+                Ok(Self::truncated_output_amount(
+                    self,
+                    input_amount,
+                    Self::safe_multiply_divide,
+                )?)
+            })
+            .collect()
+    }
+
+    /// 计算输出代币数量，在最后一步除法的向下/向上取整之间选择往返误差更小的一个
+    ///
+    /// 启发式：分别用向下取整和向上取整的输出调用 `calculate_input_amount` 反算输入，
+    /// 选择 `|反算输入 - input_amount|` 更小的输出；误差相同时选择向下取整。
+    /// 最后一步除法在精度提高或不变时是汇率乘除，在精度降低时是 `10^diff` 的除法。
+    pub fn calculate_output_amount_round_trip_optimal(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<u128, String> {
+        if input_amount == 0 {
+            return Err("Input amount must be greater than 0".to_string());
+        }
+
+        let floor_output =
+            Self::truncated_output_amount(price, input_amount, Self::safe_multiply_divide)?;
+        let ceil_output = Self::ceil_output_amount(price, input_amount, floor_output);
+
+        let round_trip_error = |output_amount: u128| {
+            Self::calculate_input_amount(price, output_amount)
+                .ok()
+                .map(|back| back.abs_diff(input_amount))
+        };
+
+        let best = match (
+            round_trip_error(floor_output),
+            ceil_output.and_then(|ceil| round_trip_error(ceil).map(|error| (ceil, error))),
+        ) {
+            (Some(floor_error), Some((ceil, ceil_error))) if ceil_error < floor_error => ceil,
+            (None, Some((ceil, _))) => ceil,
+            _ => floor_output,
+        };
+
+        if best == 0 {
+            return Err("Calculated output amount is zero, increase input amount".to_string());
+        }
+
+        Ok(best)
+    }
+
+    /// 计算输出代币数量，取最后一步除法向下/向上取整结果的平均值（向下取整）
+    ///
+    /// 精度提高时最后一步是汇率除法，取整后再放大 `10^diff`，平均值落在两者中点；
+    /// 精度不变或降低时向上取整只多 1 个最小单位，平均值等于向下取整的结果。
+    pub fn calculate_output_amount_midpoint(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<u128, String> {
+        if input_amount == 0 {
+            return Err("Input amount must be greater than 0".to_string());
+        }
+
+        let floor_output =
+            Self::truncated_output_amount(price, input_amount, Self::safe_multiply_divide)?;
+        let midpoint = match Self::ceil_output_amount(price, input_amount, floor_output) {
+            Some(ceil_output) => floor_output + (ceil_output - floor_output) / 2,
+            None => floor_output,
+        };
+
+        if midpoint == 0 {
+            return Err("Calculated output amount is zero, increase input amount".to_string());
+        }
+
+        Ok(midpoint)
+    }
+
+    /// 计算输出代币数量，最后一步除法向上或向下取整，取更接近 `target` 的一个
+    ///
+    /// 两者与 `target` 距离相同时向下取整，便于分配算法逐步逼近精确的目标总量。
+    pub fn calculate_output_amount_toward(
+        price: &PairRate,
+        input_amount: u128,
+        target: u128,
+    ) -> Result<u128, String> {
+        if input_amount == 0 {
+            return Err("Input amount must be greater than 0".to_string());
+        }
+
+        let floor_output =
+            Self::truncated_output_amount(price, input_amount, Self::safe_multiply_divide)?;
+        let output_amount = match Self::ceil_output_amount(price, input_amount, floor_output) {
+            Some(ceil_output) if ceil_output.abs_diff(target) < floor_output.abs_diff(target) => {
+                ceil_output
+            }
+            _ => floor_output,
+        };
+
+        if output_amount == 0 {
+            return Err("Calculated output amount is zero, increase input amount".to_string());
+        }
+
+        Ok(output_amount)
+    }
+
+    /// 计算最后一步除法向上取整时的输出，结果精确或溢出时返回 `None`
+    fn ceil_output_amount(
+        price: &PairRate,
+        input_amount: u128,
+        floor_output: u128,
+    ) -> Option<u128> {
+        let (input_rate, output_rate) = price.rate;
+        let (input_decimals, output_decimals) = price.decimals;
+        let (base_output, base_remainder) =
+            math::mul_div_rem(input_amount, output_rate, input_rate)?;
+
+        if output_decimals >= input_decimals {
+            if base_remainder == 0 {
+                return None;
+            }
+            let multiplier = 10u128.checked_pow((output_decimals - input_decimals) as u32)?;
+            (base_output + 1).checked_mul(multiplier)
+        } else {
+            let divisor = 10u128.checked_pow((input_decimals - output_decimals) as u32)?;
+            if base_remainder == 0 && base_output % divisor == 0 {
+                return None;
+            }
+            floor_output.checked_add(1)
+        }
+    }
+
+    /// 以整币数量的分数（如 `5/2` 个）作为输入计算输出代币数量
+    ///
+    /// 先按 `price.decimals.0` 将分数换算为输入最小单位（向下取整），再进行换算。
+    #[cfg(feature = "rational")]
+    pub fn calculate_output_from_ratio(
+        price: &PairRate,
+        input_whole: Ratio<u128>,
+    ) -> Result<u128, String> {
+        Self::validate_decimals(price.decimals)?;
+
+        let unit = 10u128.pow(price.decimals.0 as u32);
+        let input_amount = math::mul_div_floor(*input_whole.numer(), unit, *input_whole.denom())
+            .ok_or("Input ratio too large, would cause overflow".to_string())?;
+
+        Self::calculate_output_amount(price, input_amount).map_err(String::from)
+    }
+
+    /// 计算输出代币数量，截断损失超过 `max_loss` 个输入最小单位时拒绝换算
+    ///
+    /// 损失定义为输入中未体现在输出里的部分：`input_amount` 减去能换得相同输出的
+    /// 最小输入。报错时会提示无损的输入步长（输入为其整数倍时不产生截断）。
+    pub fn calculate_output_amount_max_loss(
+        price: &PairRate,
+        input_amount: u128,
+        max_loss: u128,
+    ) -> Result<u128, String> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let loss = input_amount - Self::min_input_for_output(price, output_amount)?;
+        if loss <= max_loss {
+            return Ok(output_amount);
+        }
+
+        let mut message = format!(
+            "Truncation loss {} exceeds maximum allowed {}",
+            loss, max_loss
+        );
+        if let Some(step) = Self::lossless_input_step(price) {
+            message.push_str(&format!(", use an input that is a multiple of {}", step));
+        }
+        Err(message)
+    }
+
+    /// 能换得至少 `output_amount` 输出的最小输入（按截断规则反推）
+    fn min_input_for_output(price: &PairRate, output_amount: u128) -> Result<u128, PairRateError> {
+        let (input_rate, output_rate) = price.rate;
+        let (input_decimals, output_decimals) = price.decimals;
+        let scale = 10u128.pow(input_decimals.abs_diff(output_decimals) as u32);
+        let overflow =
+            || PairRateError::overflow(OverflowStage::MultiplyDivide, (output_amount, input_rate));
+
+        if output_decimals >= input_decimals {
+            // floor(x * rate.1 / rate.0) >= ceil(output / 10^diff)
+            math::mul_div_ceil(output_amount.div_ceil(scale), input_rate, output_rate)
+                .ok_or_else(overflow)
+        } else {
+            // floor(x * rate.1 / (rate.0 * 10^diff)) >= output
+            let scaled_input_rate = input_rate.checked_mul(scale).ok_or_else(overflow)?;
+            math::mul_div_ceil(output_amount, scaled_input_rate, output_rate).ok_or_else(overflow)
+        }
+    }
+
+    /// 换算不产生截断的最小输入步长，溢出时返回 `None`
+    fn lossless_input_step(price: &PairRate) -> Option<u128> {
+        let (input_rate, output_rate) = price.rate;
+        let (input_decimals, output_decimals) = price.decimals;
+        let divisor = if output_decimals >= input_decimals {
+            input_rate
+        } else {
+            let scale = 10u128.checked_pow((input_decimals - output_decimals) as u32)?;
+            input_rate.checked_mul(scale)?
+        };
+        Some(divisor / divisor.gcd(&output_rate))
+    }
+
+    /// 计算输出代币数量，按余数比例随机向上或向下取整（种子确定则结果确定）
+    ///
+    /// 以精确结果 `input * rate.1 * 10^decimals.1 / (rate.0 * 10^decimals.0)` 为准：
+    /// 小数部分为 `p` 时以概率 `p` 向上取整，因此大量换算的期望值等于精确值，
+    /// 消除了截断带来的系统性偏差。概率以 2^-64 为分辨率。
+    pub fn calculate_output_amount_stochastic(
+        price: &PairRate,
+        input_amount: u128,
+        seed: u64,
+    ) -> Result<u128, String> {
+        if input_amount == 0 {
+            return Err("Input amount must be greater than 0".to_string());
+        }
+        Self::truncated_output_amount(price, input_amount, Self::safe_multiply_divide)?;
+
+        let (floor_output, round_up_threshold) = Self::exact_output_parts(price, input_amount)?;
+        let output_amount = if (math::splitmix64(seed) as u128) < round_up_threshold {
+            floor_output
+                .checked_add(1)
+                .ok_or("Result exceeds u128".to_string())?
+        } else {
+            floor_output
+        };
+
+        if output_amount == 0 {
+            return Err("Calculated output amount is zero, increase input amount".to_string());
+        }
+        Ok(output_amount)
+    }
+
+    /// 计算实际成交价相对现货汇率（`get_price_rate`）的偏离，单位为基点（四舍五入）
+    ///
+    /// 交易对汇率是线性的，偏离只来自输出截断：大额输入的偏离接近 0，
+    /// 主要用于发现小额输入被截断造成的粉尘级损失。输出截断为 0 时返回错误。
+    pub fn price_impact_bps(price: &PairRate, input_amount: u128) -> Result<u64, PairRateError> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let (floor_output, fraction) =
+            Self::exact_output_parts(price, input_amount).map_err(|_| {
+                PairRateError::overflow(OverflowStage::MultiplyDivide, (input_amount, price.rate.1))
+            })?;
+
+        // 偏离 = (精确输出 - 实际输出) / 精确输出。精度提高时实际输出先截断再放大，
+        // 与精确输出的差可能超过 1 个最小单位。两者都以 2^64 为小数单位表示为 256 位数，
+        // 再同时右移到 128 位以内，比值的误差远小于 1 基点。
+        let to_wide = |units: u128| (units >> 64, (units << 64) | fraction);
+        let exact_output = to_wide(floor_output);
+        let loss = to_wide(floor_output - output_amount);
+        let shift = math::bit_length_wide(exact_output.0, exact_output.1).saturating_sub(128);
+        let (_, exact_output) = math::shr_wide(exact_output.0, exact_output.1, shift);
+        let (_, loss) = math::shr_wide(loss.0, loss.1, shift);
+
+        let denominator = BPS_DENOMINATOR as u128;
+        let (bps, remainder) = math::mul_div_rem(loss, denominator, exact_output).ok_or(
+            PairRateError::overflow(OverflowStage::MultiplyDivide, (loss, denominator)),
+        )?;
+        Ok((bps + (remainder >= exact_output - remainder) as u128) as u64)
+    }
+
+    /// 统计批量换算中因截断损失的输出总量（输出最小单位，四舍五入）
+    ///
+    /// 逐个累加每个输入精确输出与截断输出之差（含输出截断为 0 的输入），用于对账时
+    /// 量化整批的舍入损失。精度提高时截断发生在放大之前，差值可能超过 1 个最小单位。
+    pub fn batch_dust(&self, inputs: &[u128]) -> Result<u128, String> {
+        const FRACTION_MASK: u128 = (1 << 64) - 1;
+        Self::validate_rate(self.rate)?;
+        Self::validate_decimals(self.decimals)?;
+
+        // 整数部分与小数部分（乘以 2^64）分开累加，小数满 1 时进位
+        let (mut whole, mut fraction_total) = (0u128, 0u128);
+        for &input_amount in inputs {
+            let output_amount =
+                Self::truncated_output_amount(self, input_amount, Self::safe_multiply_divide)?;
+            let (floor_output, fraction) = Self::exact_output_parts(self, input_amount)?;
+            fraction_total += fraction;
+            whole = whole
+                .checked_add(floor_output - output_amount)
+                .and_then(|whole| whole.checked_add(fraction_total >> 64))
+                .ok_or("Dust total overflow")?;
+            fraction_total &= FRACTION_MASK;
+        }
+        Ok(whole + ((fraction_total >> 63) & 1))
+    }
+
+    /// 估算给定置信度下的输出风险价值（VaR），单位为输出最小单位
+    ///
+    /// 假设汇率服从正态分布，`volatility_bps` 为汇率标准差（基点），损失为
+    /// `output * z * volatility`（向下取整），最多不超过全部输出。`confidence_bps` 只支持
+    /// 内置 z 值表中的常用置信度（9000、9500、9750、9900、9950、9990）。
+    pub fn value_at_risk(
+        &self,
+        input_amount: u128,
+        volatility_bps: u16,
+        confidence_bps: u16,
+    ) -> Result<u128, String> {
+        // z 值的定点放大倍数
+        const Z_SCALE: u128 = 10_000;
+        // (置信度基点, z 值 * Z_SCALE)
+        const Z_SCORES: [(u16, u128); 6] = [
+            (9_000, 12_816),
+            (9_500, 16_449),
+            (9_750, 19_600),
+            (9_900, 23_263),
+            (9_950, 25_758),
+            (9_990, 30_902),
+        ];
+
+        let z_score = Z_SCORES
+            .iter()
+            .find(|(confidence, _)| *confidence == confidence_bps)
+            .map(|&(_, z_score)| z_score)
+            .ok_or_else(|| format!("Unsupported confidence level: {} bps", confidence_bps))?;
+
+        let output_amount = Self::calculate_output_amount(self, input_amount)?;
+        let loss = math::mul_div_floor(
+            output_amount,
+            z_score * volatility_bps as u128,
+            Z_SCALE * BPS_DENOMINATOR as u128,
+        )
+        .ok_or("Value at risk exceeds u128")?;
+        Ok(loss.min(output_amount))
+    }
+
+    /// 精确输出的整数部分，以及小数部分乘以 2^64 后的整数值
+    fn exact_output_parts(price: &PairRate, input_amount: u128) -> Result<(u128, u128), String> {
+        const ONE: u128 = 1 << 64;
+        let overflow = || "Result exceeds u128".to_string();
+        let (input_rate, output_rate) = price.rate;
+        let (input_decimals, output_decimals) = price.decimals;
+        let scale = 10u128.pow(input_decimals.abs_diff(output_decimals) as u32);
+        let (base_output, base_remainder) =
+            math::mul_div_rem(input_amount, output_rate, input_rate).ok_or_else(overflow)?;
+
+        if output_decimals >= input_decimals {
+            // (q + r / rate.0) * 10^diff
+            let (extra, remainder) =
+                math::mul_div_rem(base_remainder, scale, input_rate).ok_or_else(overflow)?;
+            let floor_output = base_output
+                .checked_mul(scale)
+                .and_then(|output| output.checked_add(extra))
+                .ok_or_else(overflow)?;
+            let fraction = math::mul_div_floor(remainder, ONE, input_rate).ok_or_else(overflow)?;
+            Ok((floor_output, fraction))
+        } else {
+            // (q + r / rate.0) / 10^diff，其中 q = a * 10^diff + b
+            let (floor_output, low_digits) = (base_output / scale, base_output % scale);
+            let fraction = math::mul_div_floor(low_digits, ONE, scale).ok_or_else(overflow)?
+                + math::mul_div_floor(base_remainder, ONE, input_rate).ok_or_else(overflow)?
+                    / scale;
+            Ok((floor_output, fraction))
+        }
+    }
+
+    /// 沿路径逐跳换算，累计截断损失超过 `max_total_loss_bps` 基点时返回错误
+    ///
+    /// 每一跳的保留比例为截断输出与精确输出之比，连乘后得到相对于无截断路径的
+    /// 总保留比例（以 f64 计算）。路径为空或相邻交易对代币不衔接时返回错误。
+    pub fn route_output_bounded_loss(
+        path: &[PairRate],
+        input_amount: u128,
+        max_total_loss_bps: u16,
+    ) -> Result<u128, String> {
+        if path.is_empty() {
+            return Err("Route must contain at least one pair".to_string());
+        }
+        if let Some(hops) = path
+            .windows(2)
+            .find(|hops| hops[0].token_pair.1 != hops[1].token_pair.0)
+        {
+            return Err(format!(
+                "Route is not connected: {} -> {}",
+                hops[0].token_pair.1, hops[1].token_pair.0
+            ));
+        }
+
+        const ONE: f64 = (1u128 << 64) as f64;
+        let mut amount = input_amount;
+        let mut retained = 1.0;
+        for pair in path {
+            let output_amount = Self::calculate_output_amount(pair, amount)?;
+            // 精度提高时实际输出先截断再放大，可能比精确输出的整数部分还小
+            let (floor_output, fraction) = Self::exact_output_parts(pair, amount)?;
+            retained *= output_amount as f64 / (floor_output as f64 + fraction as f64 / ONE);
+
+            let loss_bps = (1.0 - retained) * BPS_DENOMINATOR as f64;
+            if loss_bps > max_total_loss_bps as f64 {
+                return Err(format!(
+                    "Accumulated truncation loss {:.2} bps exceeds budget {} bps",
+                    loss_bps, max_total_loss_bps
+                ));
+            }
+            amount = output_amount;
+        }
+        Ok(amount)
+    }
+
+    /// 沿路径逐跳计算输出数量，每一跳的输出作为下一跳的输入
+    ///
+    /// 要求 `hops[i].token_pair.1 == hops[i + 1].token_pair.0`，否则返回
+    /// `RouteMismatch { at_index: i }`。
+    pub fn route_output_amount(
+        hops: &[&PairRate],
+        input_amount: u128,
+    ) -> Result<u128, PairRateError> {
+        Self::validate_route(hops)?;
+        hops.iter().try_fold(input_amount, |amount, hop| {
+            Self::calculate_output_amount(hop, amount)
+        })
+    }
+
+    /// 沿路径反向逐跳计算所需输入数量，得到换出 `output_amount` 所需的首跳输入
+    pub fn route_input_amount(
+        hops: &[&PairRate],
+        output_amount: u128,
+    ) -> Result<u128, PairRateError> {
+        Self::validate_route(hops)?;
+        hops.iter().rev().try_fold(output_amount, |amount, hop| {
+            Self::calculate_input_amount(hop, amount)
+        })
+    }
+
+    /// 检查路径非空且相邻交易对首尾相接
+    fn validate_route(hops: &[&PairRate]) -> Result<(), PairRateError> {
+        if hops.is_empty() {
+            return Err(PairRateError::EmptyRoute);
+        }
+        match hops
+            .windows(2)
+            .position(|pair| pair[0].token_pair.1 != pair[1].token_pair.0)
+        {
+            Some(at_index) => Err(PairRateError::RouteMismatch { at_index }),
+            None => Ok(()),
+        }
+    }
+
+    /// 计算输出代币数量，并向下取整到 `tick`（输出最小单位）的整数倍
+    ///
+    /// 返回 `(取整后的输出, 舍去的零头)`；输出不足一个 `tick` 时取整结果为 0。
+    pub fn calculate_output_amount_output_tick(
+        price: &PairRate,
+        input_amount: u128,
+        tick: u128,
+    ) -> Result<(u128, u128), String> {
+        if tick == 0 {
+            return Err("Tick must be greater than 0".to_string());
+        }
+
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let dust = output_amount % tick;
+        Ok((output_amount - dust, dust))
+    }
+
+    /// 计算输出代币数量，并以字符串返回最后一步除法舍去的小数位
+    ///
+    /// 精度降低时最后一步是除以 `10^diff`，返回被舍去的 `diff` 位数字（保留前导零）；
+    /// 否则最后一步是汇率除法，返回余数的小数展开（最多 `MAX_DECIMALS` 位，除尽即止，
+    /// 相对于精度调整前的单位）。没有舍去任何数字时返回空字符串。
+    pub fn calculate_output_amount_with_dropped_digits(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<(u128, String), String> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let (input_rate, output_rate) = price.rate;
+        let (input_decimals, output_decimals) = price.decimals;
+        let (base_output, base_remainder) =
+            math::mul_div_rem(input_amount, output_rate, input_rate)
+                .ok_or("Result exceeds u128".to_string())?;
+
+        let dropped = if input_decimals > output_decimals {
+            let diff = (input_decimals - output_decimals) as usize;
+            let digits = base_output % 10u128.pow(diff as u32);
+            if digits == 0 {
+                String::new()
+            } else {
+                format!("{:0width$}", digits, width = diff)
+            }
+        } else {
+            let mut digits = String::new();
+            let mut remainder = base_remainder;
+            while remainder != 0 && digits.len() < MAX_DECIMALS as usize {
+                let (digit, next) = math::mul_div_rem(remainder, 10, input_rate)
+                    .ok_or("Result exceeds u128".to_string())?;
+                digits.push(char::from(b'0' + digit as u8));
+                remainder = next;
+            }
+            digits
+        };
+
+        Ok((output_amount, dropped))
+    }
+
+    /// 输入数量使用与交易对不同的精度时，先换算到 `price.decimals.0` 再计算输出
+    pub fn calculate_output_from_foreign_decimals(
+        price: &PairRate,
+        input_amount: u128,
+        input_actual_decimals: u8,
+    ) -> Result<u128, String> {
+        let normalized_input =
+            Self::adjust_decimals(input_amount, input_actual_decimals, price.decimals.0)?;
+        Self::calculate_output_amount(price, normalized_input).map_err(String::from)
+    }
+
+    /// 由整币数量与小数部分（以输入最小单位计）组合输入后计算输出
+    ///
+    /// 要求 `fractional < 10^decimals.0`。
+    pub fn calculate_output_from_parts(
+        price: &PairRate,
+        whole: u128,
+        fractional: u128,
+    ) -> Result<u128, String> {
+        Self::validate_decimals(price.decimals)?;
+        let unit = 10u128.pow(price.decimals.0 as u32);
+        if fractional >= unit {
+            return Err(format!("Fractional part must be less than {}", unit));
+        }
+
+        let input_amount = whole
+            .checked_mul(unit)
+            .and_then(|amount| amount.checked_add(fractional))
+            .ok_or("Input amount overflow")?;
+        Self::calculate_output_amount(price, input_amount).map_err(String::from)
+    }
+
+    /// 按平方根曲线计算输出：对 `isqrt(input_amount)` 应用汇率并调整精度
+    ///
+    /// 输出随输入的平方根增长，适用于联合曲线式定价；整数开方向下取整。
+    pub fn calculate_output_sqrt(price: &PairRate, input_amount: u128) -> Result<u128, String> {
+        if input_amount == 0 {
+            return Err(PairRateError::ZeroInputAmount.into());
+        }
+        Self::calculate_output_amount(price, Roots::sqrt(&input_amount)).map_err(String::from)
+    }
+
+    /// 计算输出代币数量，并拆分为 (整币数量, 不足一个整币的最小单位数量)
+    pub fn calculate_output_parts(
+        price: &PairRate,
+        input_amount: u128,
+    ) -> Result<(u128, u128), String> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let unit = 10u128.pow(price.decimals.1 as u32);
+        Ok((output_amount / unit, output_amount % unit))
+    }
+
+    /// 根据数量级粗略判断 `amount` 更像输入代币还是输出代币的最小单位数量
+    ///
+    /// 以 `10^decimals` 作为各代币“一个整币”的典型量级，比较 `amount` 的十进制位数
+    /// 与两侧精度的距离；两侧精度相同或距离相等时无法判断。仅作为排查方向错误的辅助。
+    pub fn detect_wrong_direction(&self, amount: u128) -> Result<DirectionHint, String> {
+        if amount == 0 {
+            return Err("Amount must be greater than 0".to_string());
+        }
+        Self::validate_decimals(self.decimals)?;
+
+        let magnitude = amount.ilog10() as i32;
+        let input_distance = (magnitude - self.decimals.0 as i32).abs();
+        let output_distance = (magnitude - self.decimals.1 as i32).abs();
+        Ok(match input_distance.cmp(&output_distance) {
+            core::cmp::Ordering::Less => DirectionHint::LikelyInput,
+            core::cmp::Ordering::Greater => DirectionHint::LikelyOutput,
+            core::cmp::Ordering::Equal => DirectionHint::Ambiguous,
+        })
+    }
+
+    /// 计算给定输入下输出数量对汇率的弹性
+    ///
+    /// 将输出比率上调 1 个基点，返回输出的相对变化与汇率相对变化之比。线性模型下
+    /// 约为 1.0，小额输入时截断会使结果偏离 1。
+    pub fn output_elasticity(&self, input_amount: u128) -> Result<f64, String> {
+        const BUMP_BPS: u128 = 1;
+        let denominator = BPS_DENOMINATOR as u128;
+
+        // rate.1 * (1 + BUMP_BPS / BPS_DENOMINATOR) / rate.0
+        let (input_rate, output_rate) = self.rate;
+        let bumped_rate = match (
+            input_rate.checked_mul(denominator),
+            output_rate.checked_mul(denominator + BUMP_BPS),
+        ) {
+            (Some(input_rate), Some(output_rate))
+                if input_rate <= MAX_RATE && output_rate <= MAX_RATE =>
+            {
+                (input_rate, output_rate)
+            }
+            _ => return Err("Rate too large to perturb".to_string()),
+        };
+        let bumped = PairRate {
+            rate: bumped_rate,
+            ..self.clone()
+        };
+
+        let output = Self::calculate_output_amount(self, input_amount)?;
+        let bumped_output = Self::calculate_output_amount(&bumped, input_amount)?;
+        let output_change = (bumped_output - output) as f64 / output as f64;
+        Ok(output_change * denominator as f64 / BUMP_BPS as f64)
+    }
+
+    /// 计算从 `earlier` 到当前汇率的每秒相对变化率（需要 `std` 特性）
+    ///
+    /// 以计入精度差后的汇率（`get_human_readable_rate`）计算 `(当前 / 之前 - 1) / dt_secs`，
+    /// 汇率上升为正、下降为负。两个交易对的代币对必须一致，且 `dt_secs` 不能为 0。
+    #[cfg(feature = "std")]
+    pub fn rate_of_change(&self, earlier: &PairRate, dt_secs: u64) -> Result<f64, String> {
+        if self.token_pair != earlier.token_pair {
+            return Err(format!(
+                "Token pair mismatch: {}/{} vs {}/{}",
+                self.token_pair.0, self.token_pair.1, earlier.token_pair.0, earlier.token_pair.1
+            ));
+        }
+        if dt_secs == 0 {
+            return Err("Time interval must be greater than 0".to_string());
+        }
+        Self::validate_rate(self.rate)?;
+        Self::validate_rate(earlier.rate)?;
+
+        let change = self.get_human_readable_rate() / earlier.get_human_readable_rate() - 1.0;
+        Ok(change / dt_secs as f64)
+    }
+
+    /// 先正向换算、再用反向交易对换算回来，检查损失是否不超过 `max_loss_bps` 基点
+    ///
+    /// 损失按相对于 `input_amount` 的比例计算；任一方向换算失败时返回错误。
+    pub fn round_trip_within(&self, input_amount: u128, max_loss_bps: u16) -> Result<bool, String> {
+        let output_amount = Self::calculate_output_amount(self, input_amount)?;
+        let returned = Self::calculate_output_amount(&self.invert(), output_amount)?;
+
+        let loss = input_amount.saturating_sub(returned);
+        let loss_bps = math::mul_div_ceil(loss, BPS_DENOMINATOR as u128, input_amount)
+            .ok_or("Round trip loss overflow".to_string())?;
+        Ok(loss_bps <= max_loss_bps as u128)
+    }
+
+    /// 计算给定输入下实际成交的汇率，返回 `(输入数量, 输出数量)`（均为最小单位）
+    pub fn realized_rate(&self, input_amount: u128) -> Result<(u128, u128), String> {
+        let output_amount = Self::calculate_output_amount(self, input_amount)?;
+        Ok((input_amount, output_amount))
+    }
+
+    /// 创建价格对，并上调输出比率使参考输入下的实际汇率不低于目标汇率
+    ///
+    /// 截断会让 `realized_rate(reference_input)` 略低于名义汇率。此方法保持
+    /// `target_rate.0` 不变，只把 `target_rate.1` 提高到满足目标所需的最小值。
+    pub fn with_min_realized_rate(
+        token_pair: (String, String),
+        target_rate: (u128, u128),
+        decimals: (u8, u8),
+        reference_input: u128,
+    ) -> Result<PairRate, String> {
+        Self::validate_rate(target_rate)?;
+        Self::validate_decimals(decimals)?;
+        if reference_input == 0 {
+            return Err("Reference input must be greater than 0".to_string());
+        }
+
+        let (input_rate, target_output_rate) = target_rate;
+        let overflow = || "Minimum realized rate calculation overflow".to_string();
+        let decimal_diff = decimals.0.abs_diff(decimals.1);
+        let scale = 10u128
+            .checked_pow(decimal_diff as u32)
+            .ok_or_else(overflow)?;
+
+        let output_rate = if decimals.1 >= decimals.0 {
+            // 目标输出：ceil(input * rate.1 * 10^diff / rate.0)
+            let scaled_rate = target_output_rate.checked_mul(scale).ok_or_else(overflow)?;
+            let target_output = math::mul_div_ceil(reference_input, scaled_rate, input_rate)
+                .ok_or_else(overflow)?;
+            // 需要 floor(input * r / rate.0) >= ceil(target_output / 10^diff)
+            let base_output = target_output.div_ceil(scale);
+            math::mul_div_ceil(base_output, input_rate, reference_input).ok_or_else(overflow)?
+        } else {
+            // 目标输出：ceil(input * rate.1 / (rate.0 * 10^diff))
+            let scaled_input_rate = input_rate.checked_mul(scale).ok_or_else(overflow)?;
+            let target_output =
+                math::mul_div_ceil(reference_input, target_output_rate, scaled_input_rate)
+                    .ok_or_else(overflow)?;
+            math::mul_div_ceil(target_output, scaled_input_rate, reference_input)
+                .ok_or_else(overflow)?
+        };
+
+        Self::new(
+            token_pair,
+            (input_rate, output_rate.max(target_output_rate)),
+            decimals,
+        )
+        .map_err(String::from)
+    }
+
+    /// 根据价格和输出代币数量计算需要的输入代币数量
+    pub fn calculate_input_amount(
+        price: &PairRate,
+        output_amount: u128,
+    ) -> Result<u128, PairRateError> {
+        Self::calculate_input_amount_with_rounding(price, output_amount, RoundingMode::Truncate)
+    }
+
+    /// 按指定舍入方式计算所需的输入代币数量
+    ///
+    /// 兑换合约收取输入时应使用 `RoundingMode::Ceil`，保证输入足以覆盖期望的输出。
+    pub fn calculate_input_amount_with_rounding(
+        price: &PairRate,
+        output_amount: u128,
+        mode: RoundingMode,
+    ) -> Result<u128, PairRateError> {
+        if output_amount == 0 {
+            return Err(PairRateError::ZeroOutputAmount);
+        }
+
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
+
+        let (input_rate, output_rate) = price.rate;
+
+        // 预检查：基础结果是否超出 u128（乘积本身允许超出，由乘除运算按 256 位处理）
+        if math::mul_div_floor(output_amount, input_rate, output_rate).is_none() {
+            return Err(PairRateError::overflow(
+                OverflowStage::MultiplyDivide,
+                (output_amount, input_rate),
+            ));
+        }
+
+        // 基础计算：output_amount * input_rate / output_rate
+        let base_input =
+            Self::rounded_multiply_divide(output_amount, input_rate, output_rate, mode)?;
+
+        // 精度调整：将结果从output_decimals调整到input_decimals
+        let adjusted_input = Self::adjust_decimals_with_rounding(
+            base_input,
+            price.decimals.1,
+            price.decimals.0,
+            mode,
+        )?;
+
+        if adjusted_input == 0 {
+            return Err(PairRateError::ZeroCalculatedInput);
+        }
+
+        Ok(adjusted_input)
+    }
+
+    /// 计算输出代币数量，低于 `min_output` 时返回 `SlippageExceeded`
+    ///
+    /// 对应链上路由的 `amountOutMin` 保护。
+    pub fn calculate_output_amount_checked_min(
+        price: &PairRate,
+        input_amount: u128,
+        min_output: u128,
+    ) -> Result<u128, PairRateError> {
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        if output_amount < min_output {
+            return Err(PairRateError::SlippageExceeded {
+                got: output_amount,
+                min: min_output,
+            });
+        }
+        Ok(output_amount)
+    }
+
+    /// 计算换得 `output_amount` 所需的最小输入，超出 `max_input` 时返回 `MaxInputExceeded`
+    ///
+    /// 对应链上路由的 `amountInMax` 保护。截断的 `calculate_input_amount` 反算结果可能
+    /// 换不回 `output_amount`，因此这里按正向换算能实际交付该输出的最小输入检查。
+    pub fn calculate_input_amount_checked_max(
+        price: &PairRate,
+        output_amount: u128,
+        max_input: u128,
+    ) -> Result<u128, PairRateError> {
+        if output_amount == 0 {
+            return Err(PairRateError::ZeroOutputAmount);
+        }
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
+
+        let input_amount = Self::min_input_for_output(price, output_amount)?;
+        // 确认该输入可以正常换算（不触发溢出检查）
+        Self::calculate_output_amount(price, input_amount)?;
+        if input_amount > max_input {
+            return Err(PairRateError::MaxInputExceeded {
+                required: input_amount,
+                max: max_input,
+            });
+        }
+        Ok(input_amount)
+    }
+
+    /// 计算扣除 `fee_bps` 基点手续费后的输出代币数量
+    ///
+    /// 输出为 `output * (10_000 - fee_bps) / 10_000`，向下取整。
+    pub fn calculate_output_amount_with_fee(
+        price: &PairRate,
+        input_amount: u128,
+        fee_bps: u16,
+    ) -> Result<u128, PairRateError> {
+        Self::validate_fee(fee_bps, MAX_FEE_BPS)?;
+
+        let output_amount = Self::calculate_output_amount(price, input_amount)?;
+        let net_output = math::mul_div_floor(
+            output_amount,
+            (MAX_FEE_BPS - fee_bps) as u128,
+            MAX_FEE_BPS as u128,
+        )
+        .ok_or(PairRateError::overflow(
+            OverflowStage::MultiplyDivide,
+            (output_amount, (MAX_FEE_BPS - fee_bps) as u128),
+        ))?;
+
+        if net_output == 0 {
+            return Err(PairRateError::ZeroCalculatedOutput);
+        }
+        Ok(net_output)
+    }
+
+    /// 计算扣除 `fee_bps` 基点手续费后仍能得到 `output_amount` 的最小输入
+    ///
+    /// 先将输出向上取整地还原为扣费前的数量，再按截断规则反推输入（向上取整），
+    /// 保证 `calculate_output_amount_with_fee` 的结果不低于 `output_amount`。
+    pub fn calculate_input_amount_with_fee(
+        price: &PairRate,
+        output_amount: u128,
+        fee_bps: u16,
+    ) -> Result<u128, PairRateError> {
+        if output_amount == 0 {
+            return Err(PairRateError::ZeroOutputAmount);
+        }
+        Self::validate_rate(price.rate)?;
+        Self::validate_decimals(price.decimals)?;
+        // 手续费为 100% 时任何输入都无法得到输出
+        Self::validate_fee(fee_bps, MAX_FEE_BPS - 1)?;
+
+        let gross_output = math::mul_div_ceil(
+            output_amount,
+            MAX_FEE_BPS as u128,
+            (MAX_FEE_BPS - fee_bps) as u128,
+        )
+        .ok_or(PairRateError::overflow(
+            OverflowStage::MultiplyDivide,
+            (output_amount, MAX_FEE_BPS as u128),
+        ))?;
+        let input_amount = Self::min_input_for_output(price, gross_output)?;
+
+        // 确认该输入可以正常换算（不触发溢出检查）
+        Self::calculate_output_amount_with_fee(price, input_amount, fee_bps)?;
+        Ok(input_amount)
+    }
+
+    /// 返回已将 `fee_bps` 基点手续费计入汇率的交易对
+    ///
+    /// 汇率输出比率乘以 `10000 - fee_bps`、输入比率乘以 `10000`（先约分）。
+    /// 扣费前的输出没有被截断时，新交易对的 `calculate_output_amount` 与原交易对的
+    /// `calculate_output_amount_with_fee` 结果相同；否则前者少截断一次，最多多出 1 个最小单位。
+    pub fn fee_inclusive_pair(&self, fee_bps: u16) -> Result<PairRate, String> {
+        // 手续费为 100% 时输出比率为 0
+        Self::validate_fee(fee_bps, MAX_FEE_BPS - 1)?;
+        let rate = Self::multiply_rate(
+            self.rate,
+            (MAX_FEE_BPS as u128, (MAX_FEE_BPS - fee_bps) as u128),
+        )?;
+        Self::new(self.token_pair.clone(), rate, self.decimals).map_err(String::from)
+    }
+
+    /// 检查手续费基点不超过 `max`
+    fn validate_fee(fee_bps: u16, max: u16) -> Result<(), PairRateError> {
+        if fee_bps > max {
+            return Err(PairRateError::FeeOutOfRange { fee_bps, max });
+        }
+        Ok(())
+    }
+
+    /// 检查滑点基点不超过 `BPS_DENOMINATOR`
+    fn validate_slippage(slippage_bps: u16) -> Result<(), PairRateError> {
+        if slippage_bps > BPS_DENOMINATOR {
+            return Err(PairRateError::SlippageOutOfRange {
+                slippage_bps,
+                max: BPS_DENOMINATOR,
+            });
+        }
+        Ok(())
+    }
+
+    /// 精度调整函数
+    ///
+    /// 精度差上限 `MAX_DECIMAL_DIFF` 是包含的：差值恰好等于上限时仍然允许。
+    fn adjust_decimals(
+        amount: u128,
+        from_decimals: u8,
+        to_decimals: u8,
+    ) -> Result<u128, PairRateError> {
+        Self::adjust_decimals_with_rounding(
+            amount,
+            from_decimals,
+            to_decimals,
+            RoundingMode::Truncate,
+        )
+    }
+
+    /// 与精度调整相同（截断），但精度差恰好等于 `MAX_DECIMAL_DIFF` 时也拒绝
+    ///
+    /// 供需要在上限附近预留余量的调用方使用：`10^32` 倍的放大只给数量留下约 6 位十进制空间。
+    pub fn adjust_decimals_strict(
+        amount: u128,
+        from_decimals: u8,
+        to_decimals: u8,
+    ) -> Result<u128, PairRateError> {
+        let decimal_diff = from_decimals.abs_diff(to_decimals);
+        if decimal_diff >= MAX_DECIMAL_DIFF {
+            return Err(PairRateError::DecimalDiffTooLarge {
+                diff: decimal_diff,
+                max: MAX_DECIMAL_DIFF - 1,
+            });
+        }
+        Self::adjust_decimals(amount, from_decimals, to_decimals)
+    }
+
+    /// 两种精度之间能否直接换算：精度均不超过 `MAX_DECIMALS` 且精度差不超过 `MAX_DECIMAL_DIFF`
+    pub fn can_convert(from_decimals: u8, to_decimals: u8) -> bool {
+        from_decimals <= MAX_DECIMALS
+            && to_decimals <= MAX_DECIMALS
+            && from_decimals.abs_diff(to_decimals) <= MAX_DECIMAL_DIFF
+    }
+
+    /// 精度差过大时，建议预先缩放数量的十进制位数 `k`
+    ///
+    /// 调用方先将数量按 `10^k` 缩放（即按精度差缩小 `k` 位后的精度表示），剩余精度差即可
+    /// 落在 `MAX_DECIMAL_DIFF` 之内。已可直接换算或精度超出 `MAX_DECIMALS` 时返回 `None`。
+    pub fn recommended_scaling(from_decimals: u8, to_decimals: u8) -> Option<u8> {
+        if from_decimals > MAX_DECIMALS || to_decimals > MAX_DECIMALS {
+            return None;
+        }
+        from_decimals
+            .abs_diff(to_decimals)
+            .checked_sub(MAX_DECIMAL_DIFF)
+            .filter(|&excess| excess > 0)
+    }
+
+    /// 精度调整函数，精度降低时按 `mode` 舍入
+    fn adjust_decimals_with_rounding(
+        amount: u128,
+        from_decimals: u8,
+        to_decimals: u8,
+        mode: RoundingMode,
+    ) -> Result<u128, PairRateError> {
+        // 验证精度范围
+        if from_decimals > MAX_DECIMALS || to_decimals > MAX_DECIMALS {
+            return Err(PairRateError::DecimalsExceedMax {
+                value: from_decimals.max(to_decimals),
+                max: MAX_DECIMALS,
+            });
+        }
+
+        // 检查精度差异
+        let decimal_diff = if from_decimals > to_decimals {
+            from_decimals - to_decimals
+        } else {
+            to_decimals - from_decimals
+        };
+
+        if decimal_diff > MAX_DECIMAL_DIFF {
+            return Err(PairRateError::DecimalDiffTooLarge {
+                diff: decimal_diff,
+                max: MAX_DECIMAL_DIFF,
+            });
+        }
+
+        if from_decimals == to_decimals {
+            return Ok(amount);
+        }
+
+        if from_decimals > to_decimals {
+            // 精度降低，需要除法
+            let decimal_diff = from_decimals - to_decimals;
+            let divisor =
+                10u128
+                    .checked_pow(decimal_diff as u32)
+                    .ok_or(PairRateError::overflow(
+                        OverflowStage::DecimalScaleUp,
+                        (10, decimal_diff as u128),
+                    ))?;
+            mode.round(amount / divisor, amount % divisor, divisor)
+                .ok_or(PairRateError::overflow(
+                    OverflowStage::MultiplyDivide,
+                    (amount, divisor),
+                ))
+        } else {
+            // 精度提高，需要乘法
+            let decimal_diff = to_decimals - from_decimals;
+            let multiplier =
+                10u128
+                    .checked_pow(decimal_diff as u32)
+                    .ok_or(PairRateError::overflow(
+                        OverflowStage::DecimalScaleUp,
+                        (10, decimal_diff as u128),
+                    ))?;
+            amount
+                .checked_mul(multiplier)
+                .ok_or(PairRateError::overflow(
+                    OverflowStage::DecimalScaleUp,
+                    (amount, multiplier),
+                ))
+        }
+    }
+
+    /// 安全的乘除运算，防止溢出
+    #[cfg(feature = "bigint")]
+    fn safe_multiply_divide(
+        amount: u128,
+        multiplier: u128,
+        divisor: u128,
+    ) -> Result<u128, PairRateError> {
+        Self::precheck_multiply_divide(amount, multiplier, divisor)?;
+
+        let result = BigUint::from(amount) * BigUint::from(multiplier) / BigUint::from(divisor);
+
+        result.to_u128().ok_or(PairRateError::overflow(
+            OverflowStage::MultiplyDivide,
+            (amount, multiplier),
+        ))
+    }
+
+    /// 安全的乘除运算，防止溢出（未启用 `bigint` 特性时的回退实现）
+    #[cfg(not(feature = "bigint"))]
+    fn safe_multiply_divide(
+        amount: u128,
+        multiplier: u128,
+        divisor: u128,
+    ) -> Result<u128, PairRateError> {
+        Self::checked_multiply_divide(amount, multiplier, divisor)
+    }
+
+    /// 不使用 BigUint 的乘除运算
+    fn checked_multiply_divide(
+        amount: u128,
+        multiplier: u128,
+        divisor: u128,
+    ) -> Result<u128, PairRateError> {
+        Self::precheck_multiply_divide(amount, multiplier, divisor)?;
+
+        math::mul_div_floor(amount, multiplier, divisor).ok_or(PairRateError::overflow(
+            OverflowStage::MultiplyDivide,
+            (amount, multiplier),
+        ))
+    }
+
+    /// 按 `mode` 舍入的乘除运算
+    fn rounded_multiply_divide(
+        amount: u128,
+        multiplier: u128,
+        divisor: u128,
+        mode: RoundingMode,
+    ) -> Result<u128, PairRateError> {
+        Self::precheck_multiply_divide(amount, multiplier, divisor)?;
+
+        let (quotient, remainder) = math::mul_div_rem(amount, multiplier, divisor).ok_or(
+            PairRateError::overflow(OverflowStage::MultiplyDivide, (amount, multiplier)),
+        )?;
+        mode.round(quotient, remainder, divisor)
+            .ok_or(PairRateError::overflow(
+                OverflowStage::MultiplyDivide,
+                (amount, multiplier),
+            ))
+    }
+
+    /// 乘除运算前的除零与范围检查
+    fn precheck_multiply_divide(
+        amount: u128,
+        multiplier: u128,
+        divisor: u128,
+    ) -> Result<(), PairRateError> {
+        if divisor == 0 {
+            return Err(PairRateError::DivisionByZero);
+        }
+
+        // 预检查：验证输入值范围
+        if amount > MAX_RATE || multiplier > MAX_RATE {
+            return Err(PairRateError::overflow(
+                OverflowStage::RatePrecheck,
+                (amount, multiplier),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 获取价格率，返回比率和精度
+    pub fn get_price_rate(&self) -> ((u128, u128), (u8, u8)) {
+        (self.rate, self.decimals)
+    }
+
+    /// 获取人类可读的价格率（注意：可能损失精度；需要 `std` 特性）
+    #[cfg(feature = "std")]
+    pub fn get_human_readable_rate(&self) -> f64 {
+        let (input_rate, output_rate) = self.rate;
+        let (input_decimals, output_decimals) = self.decimals;
+
+        let decimal_adjustment = if input_decimals > output_decimals {
+            10f64.powi((input_decimals - output_decimals) as i32)
+        } else {
+            1f64 / 10f64.powi((output_decimals - input_decimals) as i32)
+        };
+
+        (output_rate as f64 / input_rate as f64) * decimal_adjustment
+    }
+
+    /// 验证价格是否有效
+    pub fn is_valid(&self) -> bool {
+        Self::validate_rate(self.rate).is_ok() && Self::validate_decimals(self.decimals).is_ok()
+    }
+
+    /// 反转交易对方向：交换代币、汇率分量和精度
+    pub fn invert(&self) -> PairRate {
+        PairRate {
+            token_pair: (self.token_pair.1.clone(), self.token_pair.0.clone()),
+            rate: (self.rate.1, self.rate.0),
+            decimals: (self.decimals.1, self.decimals.0),
+        }
+    }
+
+    /// 同一流动性的反向报价：(A, B) 的汇率变为 (B, A) 的汇率，等同于 `invert`
+    ///
+    /// `calculate_output_amount(&price.inverse(), x)` 与 `calculate_input_amount(&price, x)`
+    /// 结果相同。注意截断不满足交换律：先正向再反向换算通常得不到原始数量，
+    /// 两次截断的损失会累积（见 `round_trip_within`）。
+    pub fn inverse(&self) -> PairRate {
+        self.invert()
+    }
+
+    /// 更换输入代币精度，同时调整汇率使人类可读汇率保持不变
+    ///
+    /// 汇率先化简，再尽量约去 10 的因子，结果仍需满足 `MAX_RATE` 限制。
+    pub fn rescale_input_decimals(&self, new_input_decimals: u8) -> Result<PairRate, String> {
+        Self::validate_decimals((new_input_decimals, self.decimals.1))?;
+        Self::validate_rate(self.rate)?;
+
+        let (mut input_rate, mut output_rate) = Self::reduce_rate(self.rate);
+        let old_input_decimals = self.decimals.0;
+        let overflow = || "Rescaled rate overflow".to_string();
+
+        // 新汇率 = 原汇率 * 10^(旧输入精度 - 新输入精度)
+        if new_input_decimals < old_input_decimals {
+            for _ in new_input_decimals..old_input_decimals {
+                if input_rate % 10 == 0 {
+                    input_rate /= 10;
+                } else {
+                    output_rate = output_rate.checked_mul(10).ok_or_else(overflow)?;
+                }
+            }
+        } else {
+            for _ in old_input_decimals..new_input_decimals {
+                if output_rate % 10 == 0 {
+                    output_rate /= 10;
+                } else {
+                    input_rate = input_rate.checked_mul(10).ok_or_else(overflow)?;
+                }
+            }
+        }
+
+        Self::new(
+            self.token_pair.clone(),
+            (input_rate, output_rate),
+            (new_input_decimals, self.decimals.1),
+        )
+        .map_err(String::from)
+    }
+
+    /// 将 A→B 与 B→C 两个交易对组合为 A→C 的直接汇率
+    ///
+    /// 相乘前先交叉约去公约数，结果为最简分数；若仍超出 `MAX_RATE`，返回
+    /// `RateOutOfRange`，可改用 `compose_lossy` 获取近似汇率。中间代币不一致时返回
+    /// `RouteMismatch { at_index: 0 }`。
+    ///
+    /// 中间输出没有被截断时，组合交易对的换算结果与连续两次 `calculate_output_amount`
+    /// 相同；否则组合汇率少截断一次，结果可能略大。
+    pub fn compose(&self, next: &PairRate) -> Result<PairRate, PairRateError> {
+        let ((input_hi, input_lo), (output_hi, output_lo)) = self.composed_rate(next)?;
+
+        if input_hi != 0 || output_hi != 0 || input_lo > MAX_RATE || output_lo > MAX_RATE {
+            let value = if input_hi != 0 || output_hi != 0 {
+                u128::MAX
+            } else {
+                input_lo.max(output_lo)
+            };
+            return Err(PairRateError::RateOutOfRange {
+                value,
+                max: MAX_RATE,
+            });
+        }
+
+        Self::new(
+            (self.token_pair.0.clone(), next.token_pair.1.clone()),
+            (input_lo, output_lo),
+            (self.decimals.0, next.decimals.1),
+        )
+    }
+
+    /// 与 `compose` 相同，但组合汇率超出 `MAX_RATE` 时截去两个分量的低位以近似表示
+    ///
+    /// 两个分量同时右移，较大的分量保留 127 位有效位；若汇率本身超出可表示范围
+    /// （比值大于 `MAX_RATE` 或小于 `1 / MAX_RATE`），结果会被钳制到边界附近。
+    pub fn compose_lossy(&self, next: &PairRate) -> Result<PairRate, String> {
+        let ((input_hi, input_lo), (output_hi, output_lo)) = self.composed_rate(next)?;
+
+        let max_bits = 128 - MAX_RATE.leading_zeros();
+        let bits = math::bit_length_wide(input_hi, input_lo)
+            .max(math::bit_length_wide(output_hi, output_lo));
+        let shift = bits.saturating_sub(max_bits);
+        let (_, input_rate) = math::shr_wide(input_hi, input_lo, shift);
+        let (_, output_rate) = math::shr_wide(output_hi, output_lo, shift);
+
+        Self::new(
+            (self.token_pair.0.clone(), next.token_pair.1.clone()),
+            Self::reduce_rate((input_rate.max(MIN_RATE), output_rate.max(MIN_RATE))),
+            (self.decimals.0, next.decimals.1),
+        )
+        .map_err(String::from)
+    }
+
+    /// 校验两个交易对可以首尾相接，返回交叉约分后的 256 位组合汇率
+    #[allow(clippy::type_complexity)]
+    fn composed_rate(
+        &self,
+        next: &PairRate,
+    ) -> Result<((u128, u128), (u128, u128)), PairRateError> {
+        if self.token_pair.1 != next.token_pair.0 {
+            return Err(PairRateError::RouteMismatch { at_index: 0 });
+        }
+        if self.decimals.1 != next.decimals.0 {
+            return Err(PairRateError::IntermediateDecimalsMismatch {
+                output: self.decimals.1,
+                input: next.decimals.0,
+            });
+        }
+        Self::validate_rate(self.rate)?;
+        Self::validate_rate(next.rate)?;
+        Self::validate_decimals((self.decimals.0, next.decimals.1))?;
+
+        let (first_input, first_output) = Self::reduce_rate(self.rate);
+        let (next_input, next_output) = Self::reduce_rate(next.rate);
+        let (first_input, next_output) = Self::reduce_rate((first_input, next_output));
+        let (next_input, first_output) = Self::reduce_rate((next_input, first_output));
+
+        Ok((
+            math::widening_mul(first_input, next_input),
+            math::widening_mul(first_output, next_output),
+        ))
+    }
+
+    /// 计算两个相同交易对汇率的调和平均值 `2xy / (x + y)`，结果为最简分数
+    #[cfg(feature = "bigint")]
+    pub fn harmonic_mean(a: &PairRate, b: &PairRate) -> Result<PairRate, String> {
+        if a.token_pair != b.token_pair || a.decimals != b.decimals {
+            return Err("Pairs must share token pair and decimals".to_string());
+        }
+        Self::validate_rate(a.rate)?;
+        Self::validate_rate(b.rate)?;
+
+        // x = a.1 / a.0, y = b.1 / b.0  =>  H = 2 * a.1 * b.1 / (a.1 * b.0 + b.1 * a.0)
+        let output_rate = BigUint::from(2u8) * BigUint::from(a.rate.1) * BigUint::from(b.rate.1);
+        let input_rate = BigUint::from(a.rate.1) * BigUint::from(b.rate.0)
+            + BigUint::from(b.rate.1) * BigUint::from(a.rate.0);
+        let gcd = input_rate.gcd(&output_rate);
+
+        match (
+            (input_rate / &gcd).to_u128(),
+            (output_rate / &gcd).to_u128(),
+        ) {
+            (Some(input_rate), Some(output_rate)) => {
+                Self::new(a.token_pair.clone(), (input_rate, output_rate), a.decimals)
+                    .map_err(String::from)
+            }
+            _ => Err(format!(
+                "Harmonic mean rate components must be less than {}",
+                MAX_RATE
+            )),
+        }
+    }
+
+    /// 反向汇率 `(rate.1, rate.0)`，不分配新的交易对
+    pub fn reciprocal_rate(&self) -> (u128, u128) {
+        (self.rate.1, self.rate.0)
+    }
+
+    /// 反向交易对的人类可读汇率（已计入精度差），以最简 `(输入比率, 输出比率)` 表示
+    ///
+    /// 与 `invert().get_human_readable_rate()` 表示同一数值，但保持精确。
+    pub fn reciprocal_effective_rate(&self) -> Result<(u128, u128), String> {
+        self.invert().effective_rate()
+    }
+
+    /// 人类可读汇率（已计入精度差）的最简 `(输入比率, 输出比率)` 表示
+    pub(crate) fn effective_rate(&self) -> Result<(u128, u128), String> {
+        Self::validate_rate(self.rate)?;
+        Self::validate_decimals(self.decimals)?;
+
+        // 人类可读汇率 = rate.1 * 10^decimals.0 / (rate.0 * 10^decimals.1)
+        let (mut input_rate, mut output_rate) = Self::reduce_rate(self.rate);
+        let overflow = || "Effective rate overflow".to_string();
+        let (input_decimals, output_decimals) = self.decimals;
+
+        for _ in output_decimals..input_decimals {
+            if input_rate % 10 == 0 {
+                input_rate /= 10;
+            } else {
+                output_rate = output_rate.checked_mul(10).ok_or_else(overflow)?;
+            }
+        }
+        for _ in input_decimals..output_decimals {
+            if output_rate % 10 == 0 {
+                output_rate /= 10;
+            } else {
+                input_rate = input_rate.checked_mul(10).ok_or_else(overflow)?;
+            }
+        }
+
+        Ok(Self::reduce_rate((input_rate, output_rate)))
+    }
+
+    /// 以科学计数法表示人类可读汇率，保留 `sig_digits` 位有效数字（四舍五入）
+    ///
+    /// 尾数由整数长除法逐位得到，不经过浮点数，例如 `"1.90e0"`、`"2.5e-3"`。
+    pub fn effective_rate_scientific(&self, sig_digits: u8) -> Result<String, String> {
+        let (digits, exponent) = self.effective_rate_digits(sig_digits)?;
+        Ok(Self::format_scientific(&digits, exponent))
+    }
+
+    /// 与 `effective_rate_scientific` 相同，但去掉尾数末尾的 0，例如 `"1.9e0"`、`"1e12"`
+    pub fn effective_rate_scientific_trimmed(&self, sig_digits: u8) -> Result<String, String> {
+        let (mut digits, exponent) = self.effective_rate_digits(sig_digits)?;
+        while digits.len() > 1 && digits.ends_with('0') {
+            digits.pop();
+        }
+        Ok(Self::format_scientific(&digits, exponent))
+    }
+
+    /// 人类可读汇率的前 `sig_digits` 位有效数字（四舍五入）及十进制指数
+    fn effective_rate_digits(&self, sig_digits: u8) -> Result<(String, i32), String> {
+        if sig_digits == 0 {
+            return Err("Significant digits must be greater than 0".to_string());
+        }
+        Self::validate_rate(self.rate)?;
+        Self::validate_decimals(self.decimals)?;
+
+        // 人类可读汇率 = (rate.1 / rate.0) * 10^(decimals.0 - decimals.1)
+        let (input_rate, output_rate) = Self::reduce_rate(self.rate);
+        let wanted = sig_digits as usize + 1;
+        let mut digits = String::new();
+        let mut exponent = self.decimals.0 as i32 - self.decimals.1 as i32;
+
+        let integer_part = output_rate / input_rate;
+        let mut remainder = output_rate % input_rate;
+        if integer_part > 0 {
+            digits = integer_part.to_string();
+            exponent += digits.len() as i32 - 1;
+        } else {
+            exponent -= 1;
+        }
+        while digits.len() < wanted {
+            let (digit, next) = math::mul_div_rem(remainder, 10, input_rate)
+                .ok_or("Scientific notation overflow".to_string())?;
+            remainder = next;
+            if digits.is_empty() && digit == 0 {
+                exponent -= 1;
+                continue;
+            }
+            digits.push(char::from(b'0' + digit as u8));
+        }
+
+        // 按第 sig_digits + 1 位四舍五入，进位可能使指数加一
+        let round_up = digits.as_bytes()[sig_digits as usize] >= b'5';
+        let mut mantissa: Vec<u8> = digits.as_bytes()[..sig_digits as usize].to_vec();
+        if round_up {
+            let mut index = mantissa.len();
+            loop {
+                if index == 0 {
+                    mantissa.insert(0, b'1');
+                    mantissa.pop();
+                    exponent += 1;
+                    break;
+                }
+                index -= 1;
+                if mantissa[index] == b'9' {
+                    mantissa[index] = b'0';
+                } else {
+                    mantissa[index] += 1;
+                    break;
+                }
+            }
+        }
+
+        let mantissa = String::from_utf8(mantissa).expect("digits are ASCII");
+        Ok((mantissa, exponent))
+    }
+
+    /// 将有效数字与指数格式化为 `d.ddde{exponent}`
+    fn format_scientific(digits: &str, exponent: i32) -> String {
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            format!("{}e{}", first, exponent)
+        } else {
+            format!("{}.{}e{}", first, rest, exponent)
+        }
+    }
+
+    /// 将最小单位数量按 `decimals` 位精度格式化，整数部分每三位插入 `thousands_sep`
+    ///
+    /// 小数部分保留全部 `decimals` 位，例如 `"1,234,567.890000"` 或 `"1.234.567,890000"`；
+    /// `decimals` 为 0 时不输出小数分隔符。
+    pub fn format_output_localized(
+        amount: u128,
+        decimals: u8,
+        thousands_sep: char,
+        decimal_sep: char,
+    ) -> String {
+        let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+
+        let mut text = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+        for (index, digit) in whole.chars().enumerate() {
+            if index != 0 && (whole.len() - index) % 3 == 0 {
+                text.push(thousands_sep);
+            }
+            text.push(digit);
+        }
+        if !fraction.is_empty() {
+            text.push(decimal_sep);
+            text.push_str(fraction);
+        }
+        text
+    }
+
+    /// 人类可读汇率恰好为 `10^exponent` 时返回 `Some(exponent)`，否则返回 `None`
+    pub fn is_power_of_ten_rate(&self) -> Option<i8> {
+        Self::validate_rate(self.rate).ok()?;
+        Self::validate_decimals(self.decimals).ok()?;
+
+        // 精度差只贡献 10 的幂，因此最简汇率本身必须是 10 的整数次幂
+        let log10 = |mut value: u128| {
+            let mut exponent = 0i8;
+            while value % 10 == 0 {
+                value /= 10;
+                exponent += 1;
+            }
+            (value == 1).then_some(exponent)
+        };
+        let rate_exponent = match Self::reduce_rate(self.rate) {
+            (1, output_rate) => log10(output_rate)?,
+            (input_rate, 1) => -log10(input_rate)?,
+            _ => return None,
+        };
+        Some(rate_exponent + self.decimals.0 as i8 - self.decimals.1 as i8)
+    }
+
+    /// 人类可读汇率的连分数渐近分数，分母不超过 `max_denom`
+    ///
+    /// 结果按 `(输入比率, 输出比率)` 即 `(分母, 分子)` 排列，可直接作为 `rate` 使用，
+    /// 从最粗略到最精确依次给出，最后一项可能就是汇率本身；分子为 0 的渐近分数
+    /// 被跳过。汇率无效或无法用 u128 精确表示时返回空列表。
+    pub fn rate_convergents(&self, max_denom: u128) -> Vec<(u128, u128)> {
+        let (mut denominator, mut numerator) = match self.effective_rate() {
+            Ok(rate) => rate,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut convergents = Vec::new();
+        let (mut h_prev, mut h_prev2) = (1u128, 0u128);
+        let (mut k_prev, mut k_prev2) = (0u128, 1u128);
+        while denominator != 0 {
+            let term = numerator / denominator;
+            let next = term
+                .checked_mul(h_prev)
+                .and_then(|h| h.checked_add(h_prev2))
+                .zip(
+                    term.checked_mul(k_prev)
+                        .and_then(|k| k.checked_add(k_prev2)),
+                );
+            let (h, k) = match next {
+                Some((h, k)) if k <= max_denom => (h, k),
+                _ => break,
+            };
+            if h != 0 {
+                convergents.push((k, h));
+            }
+
+            (h_prev2, h_prev) = (h_prev, h);
+            (k_prev2, k_prev) = (k_prev, k);
+            (numerator, denominator) = (denominator, numerator - term * denominator);
+        }
+        convergents
+    }
+
+    /// 按代币供应量调整（rebase）更新汇率
+    ///
+    /// `input_side` 为 `true` 时调整输入代币，否则调整输出代币。`rebase_bps` 为正表示
+    /// 供应量增加、该代币单价下降：输入代币增发会降低汇率，输出代币增发会提高汇率。
+    pub fn apply_rebase(&self, input_side: bool, rebase_bps: i32) -> Result<PairRate, String> {
+        let rebased_supply = BPS_DENOMINATOR as i64 + rebase_bps as i64;
+        if rebased_supply <= 0 {
+            return Err(format!(
+                "Rebase of {} bps would leave no supply",
+                rebase_bps
+            ));
+        }
+
+        let factor = if input_side {
+            (rebased_supply as u128, BPS_DENOMINATOR as u128)
+        } else {
+            (BPS_DENOMINATOR as u128, rebased_supply as u128)
+        };
+        let rate = Self::multiply_rate(self.rate, factor)?;
+        Self::new(self.token_pair.clone(), rate, self.decimals).map_err(String::from)
+    }
+
+    /// 汇率（输出/输入）乘以 `mul` 再除以 `div`，两者均以 `(分子, 分母)` 表示
+    ///
+    /// 例如手续费与 rebase 的组合调整。先将两个分数合并并交叉约分，结果化简后
+    /// 超出 `MAX_RATE` 时返回错误。
+    pub fn scale_rate(&self, mul: (u128, u128), div: (u128, u128)) -> Result<PairRate, String> {
+        if mul.0 == 0 || mul.1 == 0 || div.0 == 0 || div.1 == 0 {
+            return Err("Scale components must be greater than 0".to_string());
+        }
+
+        // mul / div = (mul.0 * div.1) / (mul.1 * div.0)
+        let (mul_numerator, div_numerator) = Self::reduce_rate((mul.0, div.0));
+        let (mul_denominator, div_denominator) = Self::reduce_rate((mul.1, div.1));
+        let overflow = || format!("Scale factor exceeds {}", u128::MAX);
+        let factor = (
+            mul_denominator
+                .checked_mul(div_numerator)
+                .ok_or_else(overflow)?,
+            mul_numerator
+                .checked_mul(div_denominator)
+                .ok_or_else(overflow)?,
+        );
+
+        let rate = Self::multiply_rate(self.rate, factor)?;
+        Self::new(self.token_pair.clone(), rate, self.decimals).map_err(String::from)
+    }
+
+    /// 汇率乘以一个分数因子（同为 `(输入比率, 输出比率)` 形式），交叉约分后检查溢出
+    fn multiply_rate(rate: (u128, u128), factor: (u128, u128)) -> Result<(u128, u128), String> {
+        Self::validate_rate(rate)?;
+        if factor.0 == 0 || factor.1 == 0 {
+            return Err("Rate factor components must be greater than 0".to_string());
+        }
+
+        let (input_rate, output_rate) = Self::reduce_rate(rate);
+        let (factor_input, factor_output) = Self::reduce_rate(factor);
+        let (input_rate, factor_output) = Self::reduce_rate((input_rate, factor_output));
+        let (factor_input, output_rate) = Self::reduce_rate((factor_input, output_rate));
+
+        let overflow = || format!("Rate components must be less than {}", MAX_RATE);
+        let input_rate = input_rate.checked_mul(factor_input).ok_or_else(overflow)?;
+        let output_rate = output_rate
+            .checked_mul(factor_output)
+            .ok_or_else(overflow)?;
+        Self::validate_rate((input_rate, output_rate))?;
+        Ok((input_rate, output_rate))
+    }
+
+    /// 按置信度加权平均多个报价源的汇率
+    ///
+    /// 每个报价源为 `(交易对, 置信度权重)`，要求代币和精度一致且总权重大于 0。
+    /// 加权平均按精确分数计算，化简后仍超出 `MAX_RATE` 时截去低位近似表示。
+    #[cfg(feature = "bigint")]
+    pub fn blend_by_confidence(feeds: &[(PairRate, u32)]) -> Result<PairRate, String> {
+        let (first, _) = feeds.first().ok_or("No feeds to blend".to_string())?;
+        if feeds
+            .iter()
+            .any(|(feed, _)| feed.token_pair != first.token_pair || feed.decimals != first.decimals)
+        {
+            return Err("Feeds must share token pair and decimals".to_string());
+        }
+        if feeds.iter().all(|&(_, weight)| weight == 0) {
+            return Err("Total confidence weight must be greater than 0".to_string());
+        }
+
+        // Σ w_i * rate_i.1 / rate_i.0，以 (分母, 分子) 累加
+        let mut input_rate = BigUint::from(1u8);
+        let mut output_rate = BigUint::from(0u8);
+        let mut total_weight = 0u64;
+        for (feed, weight) in feeds {
+            Self::validate_rate(feed.rate)?;
+            let feed_input = BigUint::from(feed.rate.0);
+            output_rate = output_rate * &feed_input
+                + BigUint::from(*weight) * BigUint::from(feed.rate.1) * &input_rate;
+            input_rate *= feed_input;
+            total_weight += *weight as u64;
+        }
+        input_rate *= total_weight;
+
+        let rate = Self::fit_big_rate(input_rate, output_rate);
+        Self::new(first.token_pair.clone(), rate, first.decimals).map_err(String::from)
+    }
+
+    /// 化简 BigUint 汇率，仍超出 `MAX_RATE` 时两个分量同时右移以近似表示
+    #[cfg(feature = "bigint")]
+    fn fit_big_rate(input_rate: BigUint, output_rate: BigUint) -> (u128, u128) {
+        let gcd = input_rate.gcd(&output_rate);
+        let (input_rate, output_rate) = (input_rate / &gcd, output_rate / &gcd);
+
+        let max_bits = (128 - MAX_RATE.leading_zeros()) as u64;
+        let shift = input_rate
+            .bits()
+            .max(output_rate.bits())
+            .saturating_sub(max_bits);
+        let (input_rate, output_rate) = (input_rate >> shift, output_rate >> shift);
+        Self::reduce_rate((
+            input_rate.to_u128().unwrap_or(MAX_RATE).max(MIN_RATE),
+            output_rate.to_u128().unwrap_or(MAX_RATE).max(MIN_RATE),
+        ))
+    }
+
+    /// 批量反转交易对，保持原有顺序
+    pub fn invert_all(pairs: &[PairRate]) -> Vec<PairRate> {
+        pairs.iter().map(PairRate::invert).collect()
+    }
+
+    /// 枚举从 `from` 出发、不超过 `max_depth` 跳的所有路径及其组合后的人类可读汇率
+    ///
+    /// 交易对可反向使用；路径不重复经过同一代币，以防止环路。每条路径以代币序列
+    /// （含 `from`）表示，汇率为最简 `(输入比率, 输出比率)`；组合失败（溢出或中间精度
+    /// 不一致）的路径及其延伸路径会被跳过。结果按深度优先顺序排列。
+    pub fn reachable_rates(
+        pairs: &[PairRate],
+        from: &str,
+        max_depth: u8,
+    ) -> Vec<(Vec<String>, (u128, u128))> {
+        let mut reachable = Vec::new();
+        let mut path = vec![from.to_string()];
+        Self::collect_reachable(pairs, None, &mut path, max_depth, &mut reachable);
+        reachable
+    }
+
+    /// `reachable_rates` 的深度优先搜索，`composed` 为当前路径的组合交易对
+    fn collect_reachable(
+        pairs: &[PairRate],
+        composed: Option<&PairRate>,
+        path: &mut Vec<String>,
+        remaining_depth: u8,
+        reachable: &mut Vec<(Vec<String>, (u128, u128))>,
+    ) {
+        if remaining_depth == 0 {
+            return;
+        }
+        let token = path
+            .last()
+            .expect("path always contains the start token")
+            .clone();
+
+        for pair in pairs {
+            let hop = if pair.token_pair.0 == token {
+                pair.clone()
+            } else if pair.token_pair.1 == token {
+                pair.invert()
+            } else {
+                continue;
+            };
+            if path.contains(&hop.token_pair.1) {
+                continue;
+            }
+
+            let next = match composed {
+                Some(composed) => composed.compose(&hop).ok(),
+                None => Some(hop.clone()),
+            };
+            path.push(hop.token_pair.1.clone());
+            if let Some(rate) = next.as_ref().and_then(|next| next.effective_rate().ok()) {
+                reachable.push((path.clone(), rate));
+            }
+            if let Some(next) = &next {
+                Self::collect_reachable(pairs, Some(next), path, remaining_depth - 1, reachable);
+            }
+            path.pop();
+        }
+    }
+
+    /// 求两个交易对换算出相同（且非零）输出的最小输入
+    ///
+    /// 两个交易对必须是相同代币和精度。汇率相同时任何输入的输出都相同，返回能换得
+    /// 非零输出的最小输入；汇率不同时，两者只在截断后落入同一个输出值的小段输入上
+    /// 相等，输入增大后差距超过一个截断步长即不再相交，此时返回 `None`。
+    ///
+    /// 通过类欧几里得求和统计区间内的相等点并二分查找，不逐个枚举输入，
+    /// 汇率极其接近时也能很快返回。
+    #[cfg(feature = "bigint")]
+    pub fn crossover_input(a: &PairRate, b: &PairRate) -> Result<Option<u128>, String> {
+        if a.token_pair != b.token_pair || a.decimals != b.decimals {
+            return Err("Pairs must share token pair and decimals".to_string());
+        }
+        Self::validate_rate(a.rate)?;
+        Self::validate_rate(b.rate)?;
+        Self::validate_decimals(a.decimals)?;
+
+        // 精度降低时先乘除再除以 10^k，等价于直接按 rate.1 / (rate.0 * 10^k) 截断；
+        // 精度提高时乘以 10^k 不影响是否相等。因此两者输出相等当且仅当
+        // floor(x * p / q) == floor(x * hp / hq)，其中 p / q <= hp / hq。
+        let (input_decimals, output_decimals) = a.decimals;
+        let step = BigUint::from(10u8).pow(input_decimals.saturating_sub(output_decimals) as u32);
+        let slope = |price: &PairRate| {
+            (
+                BigUint::from(price.rate.1),
+                BigUint::from(price.rate.0) * &step,
+            )
+        };
+        let (mut low, mut high) = (slope(a), slope(b));
+        if &low.0 * &high.1 > &high.0 * &low.1 {
+            core::mem::swap(&mut low, &mut high);
+        }
+        let ((p, q), (hp, hq)) = (low, high);
+
+        // 输出非零的最小输入
+        let first = (&q + &p - 1u8) / &p;
+        // x * (hp / hq - p / q) < 1 时两者的截断值最多相差 1，之后不会再相等
+        let gap = &hp * &q - &p * &hq;
+        let last = if gap == BigUint::ZERO {
+            first.clone()
+        } else {
+            (&hq * &q - 1u8) / &gap
+        };
+        if first > last {
+            return Ok(None);
+        }
+
+        // [first, x] 内输出相等的输入个数：输入个数减去两者截断值之和的差
+        let floor_total = |x: &BigUint, num: &BigUint, den: &BigUint| {
+            Self::floor_sum(x + 1u8, den.clone(), num.clone(), BigUint::ZERO)
+                - Self::floor_sum(first.clone(), den.clone(), num.clone(), BigUint::ZERO)
+        };
+        let equal_count =
+            |x: &BigUint| x + 1u8 - &first + floor_total(x, &p, &q) - floor_total(x, &hp, &hq);
+        if equal_count(&last) == BigUint::ZERO {
+            return Ok(None);
+        }
+
+        // 二分查找第一个相等点
+        let (mut lo, mut hi) = (first.clone(), last);
+        while lo < hi {
+            let mid = (&lo + &hi) / 2u8;
+            if equal_count(&mid) == BigUint::ZERO {
+                lo = mid + 1u8;
+            } else {
+                hi = mid;
+            }
+        }
+
+        // 该输入超出可计算范围时，更大的输入同样无法换算
+        let input_amount = match lo.to_u128() {
+            Some(input_amount) => input_amount,
+            None => return Ok(None),
+        };
+        match (
+            Self::calculate_output_amount(a, input_amount),
+            Self::calculate_output_amount(b, input_amount),
+        ) {
+            (Ok(a_output), Ok(b_output)) if a_output == b_output => Ok(Some(input_amount)),
+            _ => Ok(None),
+        }
+    }
+
+    /// 计算 `Σ_{i=0}^{n-1} floor((a * i + b) / m)`（类欧几里得算法）
+    #[cfg(feature = "bigint")]
+    fn floor_sum(mut n: BigUint, mut m: BigUint, mut a: BigUint, mut b: BigUint) -> BigUint {
+        let mut sum = BigUint::ZERO;
+        if n == BigUint::ZERO {
+            return sum;
+        }
+        loop {
+            if a >= m {
+                sum += (&n * (&n - 1u8)) / 2u8 * (&a / &m);
+                a %= &m;
+            }
+            if b >= m {
+                sum += &n * (&b / &m);
+                b %= &m;
+            }
+            let y_max = &a * &n + &b;
+            if y_max < m {
+                return sum;
+            }
+            n = &y_max / &m;
+            b = y_max % &m;
+            core::mem::swap(&mut m, &mut a);
+        }
+    }
+
+    /// 计算把 `total_input` 分配给 `a`、`b` 两个交易对，使两边输出尽量相等的方案
+    ///
+    /// 两个交易对必须是相同代币和精度。先按汇率求出精确平衡点，
+    /// 再在其附近比较截断后的实际输出，返回 `(给 a 的输入, 给 b 的输入)`。
+    #[cfg(feature = "bigint")]
+    pub fn equalizing_split(
+        a: &PairRate,
+        b: &PairRate,
+        total_input: u128,
+    ) -> Result<(u128, u128), String> {
+        if a.token_pair != b.token_pair || a.decimals != b.decimals {
+            return Err("Pairs must share token pair and decimals".to_string());
+        }
+        Self::validate_rate(a.rate)?;
+        Self::validate_rate(b.rate)?;
+        Self::validate_decimals(a.decimals)?;
+
+        // x * a.1 / a.0 = (T - x) * b.1 / b.0  =>  x = T * b.1 * a.0 / (a.1 * b.0 + b.1 * a.0)
+        let a_weight = BigUint::from(b.rate.1) * BigUint::from(a.rate.0);
+        let b_weight = BigUint::from(a.rate.1) * BigUint::from(b.rate.0);
+        let balance = (BigUint::from(total_input) * &a_weight / (&a_weight + &b_weight))
+            .to_u128()
+            .ok_or("Split calculation overflow".to_string())?;
+
+        let output_gap = |input_a: u128| -> Result<u128, String> {
+            let output_a = Self::truncated_output_amount(a, input_a, Self::safe_multiply_divide)?;
+            let output_b = Self::truncated_output_amount(
+                b,
+                total_input - input_a,
+                Self::safe_multiply_divide,
+            )?;
+            Ok(output_a.abs_diff(output_b))
+        };
+
+        let mut best = (balance, output_gap(balance)?);
+        for candidate in [balance.checked_sub(1), balance.checked_add(1)]
+            .into_iter()
+            .flatten()
+            .filter(|&candidate| candidate <= total_input)
+        {
+            let gap = output_gap(candidate)?;
+            if gap < best.1 {
+                best = (candidate, gap);
+            }
+        }
+
+        Ok((best.0, total_input - best.0))
+    }
+
+    /// 精确判断人类可读汇率是否位于 `[min_price, max_price]` 区间内
+    ///
+    /// 区间边界为十进制字符串（如 `"1.5"`），比较过程不经过浮点数。
+    #[cfg(feature = "bigint")]
+    pub fn is_within_price_range(&self, min_price: &str, max_price: &str) -> Result<bool, String> {
+        let min_price = Self::parse_decimal(min_price)?;
+        let max_price = Self::parse_decimal(max_price)?;
+        if Self::compare_ratios(&min_price, &max_price) == core::cmp::Ordering::Greater {
+            return Err("Minimum price must not exceed maximum price".to_string());
+        }
+
+        let rate = self.effective_rate_ratio();
+        Ok(
+            Self::compare_ratios(&rate, &min_price) != core::cmp::Ordering::Less
+                && Self::compare_ratios(&rate, &max_price) != core::cmp::Ordering::Greater,
+        )
+    }
+
+    /// 计入精度差后的汇率的精确分数表示 (分子, 分母)，不经过有损的 f64
+    #[cfg(feature = "bigint")]
+    pub fn effective_rate_ratio(&self) -> (BigUint, BigUint) {
+        let (input_rate, output_rate) = self.rate;
+        let (input_decimals, output_decimals) = self.decimals;
+        (
+            BigUint::from(output_rate) * BigUint::from(10u8).pow(input_decimals as u32),
+            BigUint::from(input_rate) * BigUint::from(10u8).pow(output_decimals as u32),
+        )
+    }
+
+    /// 通过交叉相乘比较两个分数
+    #[cfg(feature = "bigint")]
+    fn compare_ratios(a: &(BigUint, BigUint), b: &(BigUint, BigUint)) -> core::cmp::Ordering {
+        (&a.0 * &b.1).cmp(&(&b.0 * &a.1))
+    }
+
+    /// 将非负十进制字符串精确解析为分数 (分子, 分母)
+    #[cfg(feature = "bigint")]
+    fn parse_decimal(value: &str) -> Result<(BigUint, BigUint), String> {
+        let value = value.trim();
+        let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() && fraction.is_empty() || !is_digits(integer) || !is_digits(fraction)
+        {
+            return Err(format!("Invalid decimal number: {:?}", value));
+        }
+
+        let digits = format!("{}{}", integer, fraction);
+        let numerator = BigUint::parse_bytes(digits.as_bytes(), 10)
+            .ok_or_else(|| format!("Invalid decimal number: {:?}", value))?;
+        let denominator = BigUint::from(10u8).pow(fraction.len() as u32);
+        Ok((numerator, denominator))
+    }
+
+    /// 计算能够无损表示汇率的最小输出精度
+    ///
+    /// 返回最小的输出精度，使得 1 个输入最小单位按当前汇率换算后恰好是非零整数个
+    /// 输出最小单位。化简后汇率分母含 2 和 5 以外的质因子时不存在这样的精度，
+    /// 此时返回 `MAX_DECIMALS`。
+    pub fn min_decimals_for_lossless(&self) -> u8 {
+        let (input_rate, output_rate) = Self::reduce_rate(self.rate);
+        if input_rate == 0 || output_rate == 0 {
+            return MAX_DECIMALS;
+        }
+
+        let (input_twos, input_fives, rest) = Self::split_factors_of_ten(input_rate);
+        if rest != 1 {
+            return MAX_DECIMALS;
+        }
+        let (output_twos, output_fives, _) = Self::split_factors_of_ten(output_rate);
+
+        // 需要 input_rate * 10^decimals.0 整除 output_rate * 10^d
+        let input_decimals = self.decimals.0 as u32;
+        let needed = (input_twos + input_decimals)
+            .saturating_sub(output_twos)
+            .max((input_fives + input_decimals).saturating_sub(output_fives));
+
+        needed.min(MAX_DECIMALS as u32) as u8
+    }
+
+    /// 分解出因子 2 和 5 的个数，返回 (2 的个数, 5 的个数, 剩余部分)
+    fn split_factors_of_ten(mut value: u128) -> (u32, u32, u128) {
+        let twos = value.trailing_zeros();
+        value >>= twos;
+        let mut fives = 0;
+        while value % 5 == 0 {
+            value /= 5;
+            fives += 1;
+        }
+        (twos, fives, value)
+    }
+
+    /// 返回汇率化为最简分数后的交易对，代币对与精度不变
+    ///
+    /// 比值不变，因此 `calculate_output_amount` 等换算结果与化简前相同；
+    /// 化简后汇率相同的交易对在 `PartialEq`/`Hash` 下才视为相同。
+    pub fn reduced(&self) -> PairRate {
+        PairRate {
+            rate: Self::reduce_rate(self.rate),
+            ..self.clone()
+        }
+    }
+
+    /// 原地将汇率化为最简分数，见 `reduced`
+    pub fn reduce(&mut self) {
+        self.rate = Self::reduce_rate(self.rate);
+    }
+
+    /// 将汇率的两个分量同除以最大公约数
+    pub(crate) fn reduce_rate(rate: (u128, u128)) -> (u128, u128) {
+        let gcd = rate.0.gcd(&rate.1);
+        if gcd == 0 {
+            return rate;
+        }
+        (rate.0 / gcd, rate.1 / gcd)
+    }
+
+    /// 将十进制小数字符串精确换算为化简后的 `(输入比率, 输出比率)`
+    fn parse_rate(text: &str) -> Result<(u128, u128), PairRateError> {
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let fraction = fraction.trim_end_matches('0');
+        if (whole.is_empty() && fraction.is_empty())
+            || !whole
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|byte| byte.is_ascii_digit())
+        {
+            return Err(PairRateError::InvalidFormat);
+        }
+
+        let out_of_range = PairRateError::RateOutOfRange {
+            value: u128::MAX,
+            max: MAX_RATE,
+        };
+        let denominator = u32::try_from(fraction.len())
+            .ok()
+            .and_then(|len| 10u128.checked_pow(len))
+            .ok_or(out_of_range.clone())?;
+        let numerator = whole
+            .bytes()
+            .chain(fraction.bytes())
+            .try_fold(0u128, |acc, byte| {
+                acc.checked_mul(10)?.checked_add((byte - b'0') as u128)
+            })
+            .ok_or(out_of_range)?;
+        Ok(Self::reduce_rate((denominator, numerator)))
+    }
+}
+
+/// 默认交易对使用占位代币名 `TOKEN_A`/`TOKEN_B`、1:1 汇率与 18 位精度，
+/// 用作映射键前应替换为真实代币符号。
+impl Default for PairRate {
+    fn default() -> Self {
+        PairRate {
+            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            rate: (1, 1),       // 默认1:1价格
+            decimals: (18, 18), // 默认18位精度
+        }
+    }
+}
+
+/// 按 `effective_rate_ratio` 交叉相乘精确比较汇率
+///
+/// 汇率相同时再依次比较代币对、原始汇率与精度，以与 `Eq` 保持一致。
+#[cfg(feature = "bigint")]
+impl Ord for PairRate {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        Self::compare_ratios(&self.effective_rate_ratio(), &other.effective_rate_ratio())
+            .then_with(|| self.token_pair.cmp(&other.token_pair))
+            .then_with(|| self.rate.cmp(&other.rate))
+            .then_with(|| self.decimals.cmp(&other.decimals))
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl PartialOrd for PairRate {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 按 `A/B=<汇率>@<输入精度>:<输出精度>` 格式显示，汇率最多保留 18 位小数
+///
+/// 汇率与 `get_human_readable_rate` 含义相同（`rate.1 * 10^decimals.0 / (rate.0 * 10^decimals.1)`），
+/// 但按十进制精确展开、截断而不经过 f64。
+impl fmt::Display for PairRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MAX_FRACTION_DIGITS: usize = 18;
+        let (input_rate, output_rate) = self.rate;
+        let (input_decimals, output_decimals) = self.decimals;
+        write!(f, "{}/{}=", self.token_pair.0, self.token_pair.1)?;
+        if input_rate == 0 {
+            f.write_str("NaN")?;
+        } else {
+            // 展开 rate.1 / rate.0 的十进制数字，再把小数点右移 decimals.0 - decimals.1 位
+            let mut digits = (output_rate / input_rate).to_string();
+            let point = digits.len() as isize + input_decimals as isize - output_decimals as isize;
+            let mut remainder = output_rate % input_rate;
+            while (digits.len() as isize) < point + MAX_FRACTION_DIGITS as isize {
+                let (digit, next) = math::mul_div_rem(remainder, 10, input_rate)
+                    .expect("remainder is less than the divisor");
+                digits.push(char::from(b'0' + digit as u8));
+                remainder = next;
+            }
+            let point = if point < 1 {
+                digits.insert_str(0, &"0".repeat((1 - point) as usize));
+                1
+            } else {
+                point as usize
+            };
+
+            let whole = digits[..point].trim_start_matches('0');
+            f.write_str(if whole.is_empty() { "0" } else { whole })?;
+            let fraction = digits[point..point + MAX_FRACTION_DIGITS].trim_end_matches('0');
+            if !fraction.is_empty() {
+                write!(f, ".{}", fraction)?;
+            }
+        }
+        write!(f, "@{}:{}", self.decimals.0, self.decimals.1)
+    }
+}
+
+/// 解析 `A/B=<汇率>@<输入精度>:<输出精度>` 格式，例如 `"TOKEN_A/TOKEN_B=2.5@18:18"`
+///
+/// 汇率按 `from_human_readable` 解析，与 `Display` 和 `get_human_readable_rate` 含义相同；
+/// 格式错误返回 `PairRateError::InvalidFormat`，汇率与精度的校验与 `new()` 相同。
+impl core::str::FromStr for PairRate {
+    type Err = PairRateError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (tokens, rest) = value.split_once('=').ok_or(PairRateError::InvalidFormat)?;
+        let (input_token, output_token) =
+            tokens.split_once('/').ok_or(PairRateError::InvalidFormat)?;
+        let (rate, decimals) = rest.rsplit_once('@').ok_or(PairRateError::InvalidFormat)?;
+        let (input_decimals, output_decimals) = decimals
+            .split_once(':')
+            .ok_or(PairRateError::InvalidFormat)?;
+        if input_token.is_empty() || output_token.is_empty() || output_token.contains('/') {
+            return Err(PairRateError::InvalidFormat);
+        }
+
+        let parse_decimals = |text: &str| -> Result<u8, PairRateError> {
+            if text.is_empty() || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+                return Err(PairRateError::InvalidFormat);
+            }
+            text.parse().map_err(|_| PairRateError::InvalidFormat)
+        };
+        let decimals = (
+            parse_decimals(input_decimals)?,
+            parse_decimals(output_decimals)?,
+        );
+
+        PairRate::from_human_readable(
+            (input_token.to_string(), output_token.to_string()),
+            rate,
+            decimals,
+        )
+    }
+}
+
+/// 一次报价的审计记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteRecord {
+    pub pair: PairRate,
+    pub input_amount: u128,
+    pub output_amount: u128,
+    /// 时间戳占位，由调用方按需填写（默认 0）
+    pub timestamp: u64,
+}
+
+/// 带代币符号与精度的数量
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountWithMeta {
+    /// 最小单位数量
+    pub value: u128,
+    pub token: String,
+    pub decimals: u8,
+}
+
+/// `quote` 返回的完整报价
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    /// 输入数量（最小单位）
+    pub input_amount: u128,
+    /// 截断后的输出数量（最小单位）
+    pub output_amount: u128,
+    /// 实际成交比率 `(输入, 输出)`，按最小单位计并化为最简，不同于名义汇率 `rate`
+    pub effective_rate: (u128, u128),
+    /// 换得相同输出的最小输入之外多付的输入数量，即被截断吞掉的部分
+    pub dust: u128,
+}
+
+/// 换算结果不足一个输出最小单位时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZeroPolicy {
+    /// 返回错误（与 `calculate_output_amount` 一致）
+    Error,
+    /// 返回 0
+    ReturnZero,
+    /// 至少返回 1 个最小单位
+    MinOne,
+}
+
+/// `detect_wrong_direction` 的判断结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectionHint {
+    /// 数量级更接近输入代币
+    LikelyInput,
+    /// 数量级更接近输出代币，可能把输出数量当作了输入
+    LikelyOutput,
+    /// 无法区分
+    Ambiguous,
+}
+
+/// 除法结果的舍入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// 向零截断（默认，与 `calculate_output_amount` 一致）
+    #[default]
+    Truncate,
+    /// 向上取整
+    Ceil,
+    /// 向下取整（无符号数下与 `Truncate` 相同）
+    Floor,
+    /// 四舍五入，恰好一半时进位
+    HalfUp,
+    /// 四舍五入，恰好一半时取偶数
+    HalfEven,
+}
+
+impl RoundingMode {
+    /// 按舍入方式由 `dividend / divisor` 的商和余数得到结果，进位溢出时返回 `None`
+    pub(crate) fn round(self, quotient: u128, remainder: u128, divisor: u128) -> Option<u128> {
+        if remainder == 0 {
+            return Some(quotient);
+        }
+
+        // remainder < divisor，比较 remainder 与 divisor - remainder 以避免溢出
+        let round_up = match self {
+            RoundingMode::Truncate | RoundingMode::Floor => false,
+            RoundingMode::Ceil => true,
+            RoundingMode::HalfUp => remainder >= divisor - remainder,
+            RoundingMode::HalfEven => match remainder.cmp(&(divisor - remainder)) {
+                core::cmp::Ordering::Greater => true,
+                core::cmp::Ordering::Equal => quotient % 2 == 1,
+                core::cmp::Ordering::Less => false,
+            },
+        };
+
+        if round_up {
+            quotient.checked_add(1)
+        } else {
+            Some(quotient)
+        }
+    }
+}
+
+/// 输入超出可计算容量时的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapacityError {
+    /// 不会溢出的最大输入数量
+    pub max_input: u128,
+    /// 输入超出 `max_input` 的部分
+    pub excess: u128,
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.excess == 0 {
+            write!(
+                f,
+                "Conversion failed within capacity (max input {})",
+                self.max_input
+            )
+        } else {
+            write!(
+                f,
+                "Input amount exceeds capacity by {} (max input {})",
+                self.excess, self.max_input
+            )
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_output_amount() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19), // 1 input_token = 1.9 output_token
+            (24, 24),
+        )
+        .unwrap();
+
+        // 输入1个token（24位精度）
+        let input_amount = 200_000_000_000_000_000_000_000_000u128; // 1 token with 24 decimals
+        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
+        println!("Output amount: {}", output);
+    }
+
+    #[test]
+    fn test_calculate_output_amount_r() {
+        let price = PairRate::new(
+            ("TOKEN_B".to_string(), "TOKEN_A".to_string()),
+            (19, 10),
+            (24, 24),
+        )
+        .unwrap();
+
+        // 输入1个token（24位精度）
+        let input_amount = 200_000_000_000_000_000_000_000_000u128; // 1 token with 24 decimals
+        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
+        println!("Output amount: {}", output);
+    }
+
+    #[test]
+    fn test_calculate_input_amount() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (24, 18),
+        )
+        .unwrap();
+
+        // 想要输出2个token（18位精度）
+        let output_amount = 2_000_000_000_000_000_000u128; // 2 tokens with 18 decimals
+        let input = PairRate::calculate_input_amount(&price, output_amount).unwrap();
+        println!("Input amount: {}", input);
+        // 计算步骤：
+        // 1. 基础计算：2_000_000_000_000_000_000 * 1 / 2 = 1_000_000_000_000_000_000
+        // 2. 精度调整：从18位调整到24位，乘以10^6 = 1_000_000_000_000_000_000_000_000
+        assert_eq!(input, 1_000_000_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_same_decimals() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 3),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 输入1个token（18位精度）
+        let input_amount = 1_000_000_000_000_000_000u128; // 1 token
+        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
+
+        // 计算步骤：
+        // 1. 基础计算：1_000_000_000_000_000_000 * 3 / 1 = 3_000_000_000_000_000_000
+        // 2. 精度调整：精度相同，不需要调整
+        assert_eq!(output, 3_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_output_higher_decimals() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 24),
+        )
+        .unwrap();
+
+        // 输入1个token（18位精度）
+        let input_amount = 1_000_000_000_000_000_000u128; // 1 token
+        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
+
+        // 计算步骤：
+        // 1. 基础计算：1_000_000_000_000_000_000 * 2 / 1 = 2_000_000_000_000_000_000
+        // 2. 精度调整：从18位调整到24位，乘以10^6 = 2_000_000_000_000_000_000_000_000
+        assert_eq!(output, 2_000_000_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_adjust_decimals() {
+        // 测试精度调整函数
+
+        // 从高精度到低精度
+        let result = PairRate::adjust_decimals(1_000_000_000_000_000_000_000_000, 24, 18).unwrap();
+        assert_eq!(result, 1_000_000_000_000_000_000);
+
+        // 从低精度到高精度
+        let result = PairRate::adjust_decimals(1_000_000_000_000_000_000, 18, 24).unwrap();
+        assert_eq!(result, 1_000_000_000_000_000_000_000_000);
+
+        // 相同精度
+        let result = PairRate::adjust_decimals(1_000_000_000_000_000_000, 18, 18).unwrap();
+        assert_eq!(result, 1_000_000_000_000_000_000);
+    }
+
+    #[test]
+    fn test_price_consistency() {
+        let price = PairRate {
+            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            rate: (3, 5), // 3 input_token = 5 output_token
+            decimals: (24, 18),
+        };
+
+        // 测试往返计算的一致性
+        let input_amount = 3_000_000_000_000_000_000_000_000u128; // 3 tokens with 24 decimals
+        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
+        let back_to_input = PairRate::calculate_input_amount(&price, output).unwrap();
+
+        assert_eq!(back_to_input, input_amount);
+    }
+
+    #[test]
+    fn test_error_handling() {
+        let price = PairRate {
+            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            rate: (1, 1),
+            decimals: (18, 18),
+        };
+
+        // 测试输入金额为0
+        let result = PairRate::calculate_output_amount(&price, 0);
+        assert_eq!(result, Err(PairRateError::ZeroInputAmount));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Input amount must be greater than 0"
+        );
+
+        // 测试输出金额为0
+        let result = PairRate::calculate_input_amount(&price, 0);
+        assert_eq!(result, Err(PairRateError::ZeroOutputAmount));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Output amount must be greater than 0"
+        );
+
+        // 测试汇率为0
+        let invalid_price = PairRate {
+            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            rate: (0, 1),
+            decimals: (18, 18),
+        };
+        let result = PairRate::calculate_output_amount(&invalid_price, 1000);
+        assert_eq!(result, Err(PairRateError::ZeroRate));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Rate components must be greater than 0"
+        );
+
+        // 溢出
+        let result = PairRate::calculate_output_amount(&price, u128::MAX);
+        assert!(matches!(
+            result,
+            Err(PairRateError::Overflow {
+                stage: OverflowStage::RatePrecheck,
+                ..
+            })
+        ));
+        assert!(result.unwrap_err().is_overflow());
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        let price = PairRate {
+            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            rate: (1, 1),
+            decimals: (18, 18),
+        };
+
+        // 测试非常大的数字（接近 u128 上限）
+        let large_number = u128::MAX / 2;
+        let result = PairRate::calculate_output_amount(&price, large_number);
+        assert!(result.is_ok());
+
+        // 测试非常小的数字
+        let small_number = 1u128;
+        let result = PairRate::calculate_output_amount(&price, small_number);
+        assert!(result.is_ok());
+
+        // 测试精度差异较大的情况
+        let price_high_precision = PairRate {
+            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            rate: (1, 1),
+            decimals: (6, 24),
+        };
+
+        // 输入 1.0 token（6位精度）
+        let input = 1_000_000u128; // 1.0 with 6 decimals
+        let result = PairRate::calculate_output_amount(&price_high_precision, input);
+        assert!(result.is_ok());
+
+        // 期望输出：1.0 token（24位精度）
+        // 1_000_000 * 10^(24-6) = 1_000_000 * 10^18
+        let expected = input * 10u128.pow(18); // 将6位精度转换为24位精度
+        assert_eq!(result.unwrap(), expected);
+
+        // 测试反向转换
+        let result = PairRate::calculate_input_amount(&price_high_precision, expected);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), input); // 应该回到原始输入值
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_helper_functions() {
+        // 测试 is_valid
+        let valid_price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 18),
+        )
+        .unwrap();
+        assert!(valid_price.is_valid());
+
+        // 测试无效汇率
+        let invalid_result = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (0, 1),
+            (18, 18),
+        );
+        assert!(invalid_result.is_err());
+
+        // 测试超出最大汇率
+        let invalid_rate_result = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (MAX_RATE + 1, 1),
+            (18, 18),
+        );
+        assert!(invalid_rate_result.is_err());
+
+        // 测试超出最大精度
+        let invalid_decimals_result = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (MAX_DECIMALS + 1, 18),
+        );
+        assert!(invalid_decimals_result.is_err());
+
+        // 测试 get_price_rate
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (2, 5),
+            (18, 18),
+        )
+        .unwrap();
+        let ((rate_in, rate_out), (dec_in, dec_out)) = price.get_price_rate();
+        assert_eq!(rate_in, 2);
+        assert_eq!(rate_out, 5);
+        assert_eq!(dec_in, 18);
+        assert_eq!(dec_out, 18);
+
+        // 测试 human_readable_rate
+        // 1. 相同精度测试
+        let price1 = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (2, 5),
+            (18, 18),
+        )
+        .unwrap();
+        assert_eq!(price1.get_human_readable_rate(), 2.5);
+
+        // 2. 不同精度测试 (input_decimals < output_decimals)
+        let price2 = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (6, 9),
+        )
+        .unwrap();
+        assert_eq!(price2.get_human_readable_rate(), 0.001); // 1 * 10^-3
+
+        // 3. 不同精度测试 (input_decimals > output_decimals)
+        let price3 = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (9, 6),
+        )
+        .unwrap();
+        assert_eq!(price3.get_human_readable_rate(), 1000.0); // 1 * 10^3
+
+        // 测试默认值
+        let default_price = PairRate::default();
+        assert_eq!(default_price.rate, (1, 1));
+        assert_eq!(default_price.decimals, (18, 18));
+        assert_eq!(default_price.token_pair.0, "TOKEN_A");
+        assert_eq!(default_price.token_pair.1, "TOKEN_B");
+        assert!(default_price.is_valid());
+    }
+
+    #[test]
+    fn test_min_decimals_for_lossless() {
+        // 1 输入最小单位 = 1.9 输出最小单位，需要额外 1 位精度
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (0, 0),
+        )
+        .unwrap();
+        assert_eq!(price.min_decimals_for_lossless(), 1);
+
+        // 1:1 汇率下输出精度至少与输入精度相同
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 6),
+        )
+        .unwrap();
+        assert_eq!(price.min_decimals_for_lossless(), 18);
+
+        // 汇率本身放大 100 倍，可以少用 2 位精度
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 100),
+            (2, 2),
+        )
+        .unwrap();
+        assert_eq!(price.min_decimals_for_lossless(), 0);
+
+        // 1/8 需要 3 位精度
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (8, 1),
+            (0, 0),
+        )
+        .unwrap();
+        assert_eq!(price.min_decimals_for_lossless(), 3);
+
+        // 1/3 无法用十进制无损表示
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (18, 18),
+        )
+        .unwrap();
+        assert_eq!(price.min_decimals_for_lossless(), MAX_DECIMALS);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_no_bigint_matches_bigint() {
+        let prices = [
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (10, 19),
+                (24, 24),
+            )
+            .unwrap(),
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (3, 5),
+                (24, 18),
+            )
+            .unwrap(),
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (7, u128::MAX / 4),
+                (18, 18),
+            )
+            .unwrap(),
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (MAX_RATE, MAX_RATE - 1),
+                (6, 24),
+            )
+            .unwrap(),
+        ];
+        let inputs = [1u128, 3, 999_999, 10u128.pow(24), u128::MAX / 3, MAX_RATE];
+
+        for price in &prices {
+            for &input in &inputs {
+                assert_eq!(
+                    PairRate::calculate_output_amount_no_bigint(price, input),
+                    PairRate::calculate_output_amount(price, input),
+                    "rate {:?}, input {}",
+                    price.rate,
+                    input
+                );
+            }
+        }
+
+        // 超出 u128 的结果在两条路径上都报错
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 4),
+            (18, 18),
+        )
+        .unwrap();
+        assert!(PairRate::calculate_output_amount_no_bigint(&price, u128::MAX / 4 + 1).is_err());
+    }
+
+    #[test]
+    fn test_overflow_handling() {
+        let price = PairRate {
+            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            rate: (1, u128::MAX),
+            decimals: (18, 18),
+        };
+
+        // 测试乘法溢出
+        let result = PairRate::calculate_output_amount(&price, u128::MAX);
+        assert!(result.is_err());
+
+        // 测试精度调整溢出
+        let price_high_precision = PairRate {
+            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            rate: (1, 1),
+            decimals: (0, 38), // 超过最大安全精度
+        };
+        let result = PairRate::calculate_output_amount(&price_high_precision, u128::MAX / 2);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_amount_capacity() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 4),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 容量由乘法预检查决定：u128::MAX / 4
+        let max_input = u128::MAX / 4;
+        assert!(PairRate::calculate_output_amount_capacity(&price, max_input).is_ok());
+        assert_eq!(
+            PairRate::calculate_output_amount_capacity(&price, max_input + 10),
+            Err(CapacityError {
+                max_input,
+                excess: 10
+            })
+        );
+
+        // 精度提高时容量由 10^diff 的放大决定
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (6, 24),
+        )
+        .unwrap();
+        let max_input = u128::MAX / 10u128.pow(18);
+        assert!(PairRate::calculate_output_amount_capacity(&price, max_input).is_ok());
+        let err = PairRate::calculate_output_amount_capacity(&price, max_input + 1).unwrap_err();
+        assert_eq!(err.max_input, max_input);
+        assert_eq!(err.excess, 1);
+
+        // 容量内的其他失败不报告超出量
+        let err = PairRate::calculate_output_amount_capacity(&price, 0).unwrap_err();
+        assert_eq!(err.excess, 0);
+    }
+
+    #[test]
+    fn test_ladder_outputs() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 6),
+        )
+        .unwrap();
+
+        let input_amount = 2_000_000_000_000_000_000u128; // 2 tokens
+        let outputs = price
+            .ladder_outputs(input_amount, &[(10, 19), (10, 20), (10, 21)])
+            .unwrap();
+        assert_eq!(outputs, vec![3_800_000, 4_000_000, 4_200_000]);
+
+        // 无效档位返回错误
+        assert!(price
+            .ladder_outputs(input_amount, &[(1, 2), (0, 1)])
+            .is_err());
+    }
+
+    #[test]
+    fn test_cumulative_output() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (6, 3),
+        )
+        .unwrap();
+
+        let outputs = price.cumulative_output(10_000, 7).unwrap();
+        assert_eq!(outputs.len(), 8);
+        assert_eq!(outputs[0], 0);
+        // 10_000 / 3 = 3333，降低 3 位精度后为 3
+        assert_eq!(outputs[7], 3);
+        assert!(outputs.windows(2).all(|w| w[0] <= w[1]));
+
+        assert!(price.cumulative_output(10_000, 0).is_err());
+    }
+
+    #[test]
+    fn test_with_min_realized_rate() {
+        // 目标汇率 1.9，输入 7 单位时截断：7 * 19 / 10 = 13（实际 1.857）
+        let target = (10, 19);
+        let reference_input = 7u128;
+        let plain = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            target,
+            (6, 6),
+        )
+        .unwrap();
+        let (input, output) = plain.realized_rate(reference_input).unwrap();
+        assert!(output * target.0 < input * target.1);
+
+        let bumped = PairRate::with_min_realized_rate(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            target,
+            (6, 6),
+            reference_input,
+        )
+        .unwrap();
+        assert_eq!(bumped.rate, (10, 20));
+        let (input, output) = bumped.realized_rate(reference_input).unwrap();
+        assert!(output * target.0 >= input * target.1);
+
+        // 精度降低时同样满足目标
+        let bumped = PairRate::with_min_realized_rate(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            target,
+            (9, 6),
+            7_777,
+        )
+        .unwrap();
+        let (input, output) = bumped.realized_rate(7_777).unwrap();
+        assert!(output * target.0 * 1_000 >= input * target.1);
+
+        // 已经精确时不需要上调
+        let exact = PairRate::with_min_realized_rate(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            target,
+            (6, 6),
+            10,
+        )
+        .unwrap();
+        assert_eq!(exact.rate, target);
+    }
+
+    #[test]
+    fn test_calculate_output_parts() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+
+        // 1 个 TOKEN_A = 1.9 个 TOKEN_B
+        let input_amount = 1_000_000_000_000_000_000u128;
+        let parts = PairRate::calculate_output_parts(&price, input_amount).unwrap();
+        assert_eq!(parts, (1, 900_000));
+
+        // 10 个 TOKEN_A = 19 个 TOKEN_B，没有小数部分
+        let parts = PairRate::calculate_output_parts(&price, input_amount * 10).unwrap();
+        assert_eq!(parts, (19, 0));
+
+        assert!(PairRate::calculate_output_parts(&price, 0).is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_is_within_price_range() {
+        // 人类可读汇率 1.9
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (6, 6),
+        )
+        .unwrap();
+        assert!(price.is_within_price_range("1.5", "2").unwrap());
+        assert!(price.is_within_price_range("1.9", "1.90").unwrap());
+        assert!(!price.is_within_price_range("1.91", "3").unwrap());
+        assert!(!price.is_within_price_range("0.5", "1.899999999").unwrap());
+
+        // 精度不同时按人类可读汇率比较：1 * 10^-3
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (6, 9),
+        )
+        .unwrap();
+        assert!(price.is_within_price_range(".0009", "0.0011").unwrap());
+
+        assert!(price.is_within_price_range("abc", "1").is_err());
+        assert!(price.is_within_price_range("2", "1").is_err());
+        assert!(price.is_within_price_range("-1", "1").is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_amount_round_trip_optimal() {
+        // 3 个输入兑换 1 个输出，输入 5 时精确结果为 1.67
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (18, 18),
+        )
+        .unwrap();
+
+        let input_amount = 5u128;
+        let truncated = PairRate::calculate_output_amount(&price, input_amount).unwrap();
+        let optimal =
+            PairRate::calculate_output_amount_round_trip_optimal(&price, input_amount).unwrap();
+        assert_eq!(truncated, 1);
+        assert_eq!(optimal, 2);
+
+        let loss = |output| {
+            PairRate::calculate_input_amount(&price, output)
+                .unwrap()
+                .abs_diff(input_amount)
+        };
+        assert!(loss(optimal) < loss(truncated));
+
+        // 精确的换算不受影响
+        assert_eq!(
+            PairRate::calculate_output_amount_round_trip_optimal(&price, 6).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_invert_all() {
+        let pairs = vec![
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (10, 19),
+                (24, 18),
+            )
+            .unwrap(),
+            PairRate::new(
+                ("TOKEN_B".to_string(), "TOKEN_C".to_string()),
+                (1, 3),
+                (6, 6),
+            )
+            .unwrap(),
+        ];
+
+        let inverted = PairRate::invert_all(&pairs);
+        assert_eq!(inverted.len(), pairs.len());
+        for (pair, inverse) in pairs.iter().zip(&inverted) {
+            assert_eq!(*inverse, pair.invert());
+        }
+        assert_eq!(
+            inverted[0].token_pair,
+            ("TOKEN_B".to_string(), "TOKEN_A".to_string())
+        );
+        assert_eq!(inverted[0].rate, (19, 10));
+        assert_eq!(inverted[0].decimals, (18, 24));
+
+        assert_eq!(PairRate::invert_all(&inverted), pairs);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_equalizing_split() {
+        let a = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 3),
+            (6, 6),
+        )
+        .unwrap();
+        let b = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (6, 6),
+        )
+        .unwrap();
+
+        // a 的价格是 b 的 3 倍，应分得 1/4 的输入
+        let (input_a, input_b) = PairRate::equalizing_split(&a, &b, 1_000_000).unwrap();
+        assert_eq!((input_a, input_b), (250_000, 750_000));
+        assert_eq!(
+            PairRate::calculate_output_amount(&a, input_a).unwrap(),
+            PairRate::calculate_output_amount(&b, input_b).unwrap()
+        );
+
+        // 无法整除时输出差距最小
+        let (input_a, input_b) = PairRate::equalizing_split(&a, &b, 1_001).unwrap();
+        assert_eq!(input_a + input_b, 1_001);
+        let gap = PairRate::calculate_output_amount(&a, input_a)
+            .unwrap()
+            .abs_diff(PairRate::calculate_output_amount(&b, input_b).unwrap());
+        assert!(gap <= 2);
+
+        let other = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_C".to_string()),
+            (1, 1),
+            (6, 6),
+        )
+        .unwrap();
+        assert!(PairRate::equalizing_split(&a, &other, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_amount_zero_policy() {
+        // 1 个最小单位输入只能换到 0.5 个输出最小单位
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (2, 1),
+            (18, 18),
+        )
+        .unwrap();
+
+        assert!(
+            PairRate::calculate_output_amount_zero_policy(&price, 1, ZeroPolicy::Error).is_err()
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_zero_policy(&price, 1, ZeroPolicy::ReturnZero),
+            Ok(0)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_zero_policy(&price, 1, ZeroPolicy::MinOne),
+            Ok(1)
+        );
+
+        // 非零结果不受策略影响
+        assert_eq!(
+            PairRate::calculate_output_amount_zero_policy(&price, 10, ZeroPolicy::MinOne),
+            Ok(5)
+        );
+        // 零输入始终报错
+        assert!(
+            PairRate::calculate_output_amount_zero_policy(&price, 0, ZeroPolicy::ReturnZero)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_rescale_input_decimals() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (6, 6),
+        )
+        .unwrap();
+
+        // 提高输入精度
+        let rescaled = price.rescale_input_decimals(18).unwrap();
+        assert_eq!(rescaled.decimals, (18, 6));
+        assert_eq!(rescaled.rate, (10_000_000_000_000, 19));
+        assert_eq!(
+            rescaled.get_human_readable_rate(),
+            price.get_human_readable_rate()
+        );
+
+        // 降回原精度得到原汇率
+        let restored = rescaled.rescale_input_decimals(6).unwrap();
+        assert_eq!(restored, price);
+
+        // 降低输入精度
+        let rescaled = price.rescale_input_decimals(0).unwrap();
+        assert_eq!(rescaled.rate, (1, 1_900_000));
+        assert_eq!(
+            rescaled.get_human_readable_rate(),
+            price.get_human_readable_rate()
+        );
+
+        assert!(price.rescale_input_decimals(MAX_DECIMALS + 1).is_err());
+    }
+
+    #[cfg(feature = "rational")]
+    #[test]
+    fn test_calculate_output_from_ratio() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 6),
+        )
+        .unwrap();
+
+        // 2.5 个 TOKEN_A = 5 个 TOKEN_B
+        let output = PairRate::calculate_output_from_ratio(&price, Ratio::new(5, 2)).unwrap();
+        assert_eq!(output, 5_000_000);
+
+        // 1/3 个 TOKEN_A 先截断到最小单位
+        let output = PairRate::calculate_output_from_ratio(&price, Ratio::new(1, 3)).unwrap();
+        assert_eq!(output, 666_666);
+
+        assert!(PairRate::calculate_output_from_ratio(&price, Ratio::new(0, 1)).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_compose() {
+        let a_to_b = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (4, 6),
+            (18, 6),
+        )
+        .unwrap();
+        let b_to_c = PairRate::new(
+            ("TOKEN_B".to_string(), "TOKEN_C".to_string()),
+            (9, 2),
+            (6, 8),
+        )
+        .unwrap();
+
+        // 1.5 * (2/9) = 1/3
+        let a_to_c = a_to_b.compose(&b_to_c).unwrap();
+        assert_eq!(
+            a_to_c.token_pair,
+            ("TOKEN_A".to_string(), "TOKEN_C".to_string())
+        );
+        assert_eq!(a_to_c.rate, (3, 1));
+        assert_eq!(a_to_c.decimals, (18, 8));
+
+        // 中间代币或精度不一致
+        assert_eq!(
+            a_to_b.compose(&a_to_b),
+            Err(PairRateError::RouteMismatch { at_index: 0 })
+        );
+        let b_to_c_wide = PairRate {
+            decimals: (18, 8),
+            ..b_to_c.clone()
+        };
+        assert_eq!(
+            a_to_b.compose(&b_to_c_wide),
+            Err(PairRateError::IntermediateDecimalsMismatch {
+                output: 6,
+                input: 18
+            })
+        );
+
+        // 中间输出无截断时与连续两次换算结果相同
+        for input in [
+            6_000_000_000_000,
+            6_000_000_000_000_000_000,
+            18_000_000_000_000_000_000_000,
+        ] {
+            let chained = PairRate::calculate_output_amount(&a_to_b, input)
+                .and_then(|middle| PairRate::calculate_output_amount(&b_to_c, middle));
+            assert_eq!(PairRate::calculate_output_amount(&a_to_c, input), chained);
+        }
+
+        // 组合后超出 MAX_RATE
+        let big = PairRate::new(
+            ("TOKEN_B".to_string(), "TOKEN_C".to_string()),
+            (1, MAX_RATE),
+            (6, 6),
+        )
+        .unwrap();
+        assert_eq!(
+            a_to_b.compose(&big),
+            Err(PairRateError::RateOutOfRange {
+                value: u128::MAX,
+                max: MAX_RATE
+            })
+        );
+
+        // 有损组合得到范围内的近似汇率
+        let first = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10u128.pow(30) + 7, 3 * 10u128.pow(30) + 1),
+            (6, 6),
+        )
+        .unwrap();
+        let second = PairRate::new(
+            ("TOKEN_B".to_string(), "TOKEN_C".to_string()),
+            (3 * 10u128.pow(30) + 11, 2 * 10u128.pow(30) + 13),
+            (6, 6),
+        )
+        .unwrap();
+        assert!(first.compose(&second).is_err());
+        let lossy = first.compose_lossy(&second).unwrap();
+        assert!(lossy.is_valid());
+        let (input_rate, output_rate) = lossy.rate;
+        let approx = output_rate as f64 / input_rate as f64;
+        let exact = first.get_human_readable_rate() * second.get_human_readable_rate();
+        assert!((approx - exact).abs() / exact < 1e-12);
+
+        // 未超出范围时与 compose 一致
+        assert_eq!(a_to_b.compose_lossy(&b_to_c).unwrap(), a_to_c);
+    }
+
+    #[test]
+    fn test_calculate_output_logged() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 3),
+            (18, 18),
+        )
+        .unwrap();
+        let mut log = Vec::new();
+
+        assert_eq!(price.calculate_output_logged(100, &mut log).unwrap(), 300);
+        assert_eq!(price.calculate_output_logged(7, &mut log).unwrap(), 21);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].pair, price);
+        assert_eq!((log[0].input_amount, log[0].output_amount), (100, 300));
+        assert_eq!((log[1].input_amount, log[1].output_amount), (7, 21));
+        assert_eq!(log[1].timestamp, 0);
+
+        // 失败的换算不写入日志
+        assert!(price.calculate_output_logged(0, &mut log).is_err());
+        assert_eq!(log.len(), 2);
+    }
+
+    #[test]
+    fn test_calculate_output_amount_with_floor() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 汇率 1.9 高于底价 1.8
+        assert_eq!(
+            PairRate::calculate_output_amount_with_floor(&price, 1_000, (10, 18)),
+            Ok(1_900)
+        );
+        // 恰好等于底价
+        assert_eq!(
+            PairRate::calculate_output_amount_with_floor(&price, 1_000, (20, 38)),
+            Ok(1_900)
+        );
+        // 汇率低于底价 2.0
+        assert!(PairRate::calculate_output_amount_with_floor(&price, 1_000, (1, 2)).is_err());
+        assert_eq!(
+            PairRate::calculate_output_amount_clamped_to_floor(&price, 1_000, (1, 2)),
+            Ok(2_000)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_clamped_to_floor(&price, 1_000, (10, 18)),
+            Ok(1_900)
+        );
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_harmonic_mean() {
+        let a = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 18),
+        )
+        .unwrap();
+        let b = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (2, 6),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 2 * 1 * 3 / (1 + 3) = 1.5，而算术平均值为 2
+        let mean = PairRate::harmonic_mean(&a, &b).unwrap();
+        assert_eq!(mean.rate, (2, 3));
+        #[cfg(feature = "std")]
+        {
+            assert_eq!(mean.get_human_readable_rate(), 1.5);
+            assert_ne!(mean.get_human_readable_rate(), 2.0);
+        }
+
+        let other = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 6),
+        )
+        .unwrap();
+        assert!(PairRate::harmonic_mean(&a, &other).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reciprocal_rate() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 25),
+            (6, 9),
+        )
+        .unwrap();
+
+        assert_eq!(price.reciprocal_rate(), price.invert().rate);
+
+        // 人类可读汇率 2.5 * 10^-3，反向为 400
+        let (input_rate, output_rate) = price.reciprocal_effective_rate().unwrap();
+        assert_eq!((input_rate, output_rate), (1, 400));
+        assert_eq!(
+            output_rate as f64 / input_rate as f64,
+            price.invert().get_human_readable_rate()
+        );
+
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 7),
+            (18, 18),
+        )
+        .unwrap();
+        assert_eq!(price.reciprocal_effective_rate().unwrap(), (7, 3));
+        assert_eq!(3.0 / 7.0, price.invert().get_human_readable_rate());
+    }
+
+    #[test]
+    fn test_calculate_output_from_foreign_decimals() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 上游以 6 位精度给出 1.5 个 TOKEN_A
+        let output =
+            PairRate::calculate_output_from_foreign_decimals(&price, 1_500_000, 6).unwrap();
+        assert_eq!(output, 3_000_000_000_000_000_000);
+        assert_eq!(
+            output,
+            PairRate::calculate_output_amount(&price, 1_500_000_000_000_000_000).unwrap()
+        );
+
+        assert!(
+            PairRate::calculate_output_from_foreign_decimals(&price, 1, MAX_DECIMALS + 1).is_err()
+        );
+    }
+
+    #[test]
+    fn test_calculate_output_amount_max_loss() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 输入 10 只换得 3，其中 1 个最小单位被截断
+        assert_eq!(
+            PairRate::calculate_output_amount_max_loss(&price, 10, 1),
+            Ok(3)
+        );
+        let err = PairRate::calculate_output_amount_max_loss(&price, 10, 0).unwrap_err();
+        assert!(err.contains("Truncation loss 1 exceeds maximum allowed 0"));
+        assert!(err.contains("multiple of 3"));
+
+        // 无损输入
+        assert_eq!(
+            PairRate::calculate_output_amount_max_loss(&price, 9, 0),
+            Ok(3)
+        );
+
+        // 精度降低时损失也以输入最小单位计
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 15),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_max_loss(&price, 12_345, 345),
+            Ok(12)
+        );
+        let err = PairRate::calculate_output_amount_max_loss(&price, 12_345, 344).unwrap_err();
+        assert!(err.contains("multiple of 1000"));
+    }
+
+    #[test]
+    fn test_simulate() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 18),
+        )
+        .unwrap();
+
+        let balances = price.simulate(1_000, &[100, 10, 55]).unwrap();
+        // 190、19、104（104.5 截断）
+        assert_eq!(balances, vec![1_190, 1_209, 1_313]);
+
+        assert_eq!(price.simulate(5, &[]).unwrap(), Vec::<u128>::new());
+        assert!(price.simulate(0, &[100, 0]).is_err());
+        assert!(price.simulate(u128::MAX, &[100]).is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_amount_sized_slippage() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 18),
+        )
+        .unwrap();
+        let curve = [(1_000, 10), (100_000, 50), (1_000_000, 200)];
+
+        // 低于第一档不计滑点
+        assert_eq!(
+            PairRate::calculate_output_amount_sized_slippage(&price, 500, &curve),
+            Ok(500)
+        );
+        // 10 基点
+        assert_eq!(
+            PairRate::calculate_output_amount_sized_slippage(&price, 10_000, &curve),
+            Ok(9_990)
+        );
+        // 恰好落在阈值上使用该档：50 基点
+        assert_eq!(
+            PairRate::calculate_output_amount_sized_slippage(&price, 100_000, &curve),
+            Ok(99_500)
+        );
+        // 200 基点
+        assert_eq!(
+            PairRate::calculate_output_amount_sized_slippage(&price, 2_000_000, &curve),
+            Ok(1_960_000)
+        );
+
+        let unsorted = [(100_000, 50), (1_000, 10)];
+        assert!(PairRate::calculate_output_amount_sized_slippage(&price, 500, &unsorted).is_err());
+        assert!(
+            PairRate::calculate_output_amount_sized_slippage(&price, 500, &[(0, 10_001)]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 18),
+        )
+        .unwrap();
+
+        let csv = price.to_csv(&[100, u128::MAX, 7]).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "input,output,error");
+        assert_eq!(lines[1], "100,200,");
+        assert_eq!(
+            lines[2],
+            format!(
+                "{},,\"Result exceeds u128 (overflow at multiply-divide step, operands of 128 and 2 bits)\"",
+                u128::MAX
+            )
+        );
+        assert_eq!(lines[3], "7,14,");
+
+        let invalid = PairRate {
+            rate: (0, 1),
+            ..price
+        };
+        assert!(invalid.to_csv(&[1]).is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_amount_with_dropped_digits() {
+        // 13 / 8 = 1.625
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (8, 1),
+            (18, 18),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_with_dropped_digits(&price, 13).unwrap(),
+            (1, "625".to_string())
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_with_dropped_digits(&price, 16).unwrap(),
+            (2, String::new())
+        );
+
+        // 1 / 3 的小数展开截止于 MAX_DECIMALS 位
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (18, 18),
+        )
+        .unwrap();
+        let (output, dropped) =
+            PairRate::calculate_output_amount_with_dropped_digits(&price, 4).unwrap();
+        assert_eq!(output, 1);
+        assert_eq!(dropped, "3".repeat(MAX_DECIMALS as usize));
+
+        // 精度降低 5 位：1_200_345 -> 12，舍去 "00345"
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 13),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_with_dropped_digits(&price, 1_200_345).unwrap(),
+            (12, "00345".to_string())
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_apply_rebase() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 输入代币增发 25%：汇率 2 -> 1.6
+        let rebased = price.apply_rebase(true, 2_500).unwrap();
+        assert_eq!(rebased.rate, (5, 8));
+        assert!(rebased.get_human_readable_rate() < price.get_human_readable_rate());
+
+        // 输出代币增发 25%：汇率 2 -> 2.5
+        let rebased = price.apply_rebase(false, 2_500).unwrap();
+        assert_eq!(rebased.rate, (2, 5));
+        assert!(rebased.get_human_readable_rate() > price.get_human_readable_rate());
+
+        // 输入代币通缩 50%：汇率 2 -> 4
+        let rebased = price.apply_rebase(true, -5_000).unwrap();
+        assert_eq!(rebased.rate, (1, 4));
+
+        assert!(price.apply_rebase(false, -10_000).is_err());
+        assert_eq!(price.apply_rebase(true, 0).unwrap(), price);
+    }
+
+    #[test]
+    fn test_calculate_output_dynamic() {
+        use std::cell::Cell;
+
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 每次读取时汇率上升
+        let ticks = Cell::new(0u128);
+        let oracle = || {
+            ticks.set(ticks.get() + 1);
+            (10, 10 + ticks.get())
+        };
+        assert_eq!(price.calculate_output_dynamic(1_000, oracle), Ok(1_100));
+        assert_eq!(price.calculate_output_dynamic(1_000, oracle), Ok(1_200));
+        assert_eq!(price.calculate_output_dynamic(1_000, oracle), Ok(1_300));
+
+        assert!(price.calculate_output_dynamic(1_000, || (0, 1)).is_err());
+        assert_eq!(price.rate, (1, 1));
+    }
+
+    #[test]
+    fn test_calculate_output_amount_stochastic() {
+        // 5 / 4 = 1.25
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (4, 1),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 相同种子结果相同
+        let first = PairRate::calculate_output_amount_stochastic(&price, 5, 42).unwrap();
+        for _ in 0..10 {
+            assert_eq!(
+                PairRate::calculate_output_amount_stochastic(&price, 5, 42).unwrap(),
+                first
+            );
+        }
+
+        // 期望值接近精确值 1.25
+        let trials = 10_000u64;
+        let total: u128 = (0..trials)
+            .map(|seed| PairRate::calculate_output_amount_stochastic(&price, 5, seed).unwrap())
+            .sum();
+        let mean = total as f64 / trials as f64;
+        assert!((mean - 1.25).abs() < 0.02, "mean {}", mean);
+
+        // 精度降低时同样无偏：12_345 / 1000 = 12.345
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 15),
+        )
+        .unwrap();
+        let total: u128 = (0..trials)
+            .map(|seed| PairRate::calculate_output_amount_stochastic(&price, 12_345, seed).unwrap())
+            .sum();
+        let mean = total as f64 / trials as f64;
+        assert!((mean - 12.345).abs() < 0.02, "mean {}", mean);
+
+        // 精确结果不受随机数影响
+        assert_eq!(
+            PairRate::calculate_output_amount_stochastic(&price, 12_000, 7).unwrap(),
+            12
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let wrap = PairRate::wrap("BTC".to_string(), 8, 18);
+        assert_eq!(wrap.token_pair, ("BTC".to_string(), "BTC".to_string()));
+        assert_eq!(wrap.rate, (1, 1));
+        assert!(wrap.is_valid());
+        assert!(!wrap.is_identity());
+
+        // 1.5 BTC：8 位精度 -> 18 位精度
+        let wrapped = PairRate::calculate_output_amount(&wrap, 150_000_000).unwrap();
+        assert_eq!(wrapped, 1_500_000_000_000_000_000);
+
+        // 反向解包
+        let unwrap = wrap.invert();
+        assert_eq!(
+            PairRate::calculate_output_amount(&unwrap, wrapped).unwrap(),
+            150_000_000
+        );
+
+        assert!(PairRate::wrap("BTC".to_string(), 8, 8).is_identity());
+        assert!(!PairRate::default().is_identity());
+    }
+
+    #[test]
+    fn test_max_input_for_output_cap() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 18),
+        )
+        .unwrap();
+
+        let input = PairRate::max_input_for_output_cap(&price, 21_000_000).unwrap();
+        assert_eq!(input, 10_500_000);
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, input).unwrap(),
+            21_000_000
+        );
+
+        // 2 换 1 时最大输入为 MAX_RATE，最多产生 MAX_RATE / 2
+        let price = price.invert();
+        let max_output = MAX_RATE / 2;
+        assert_eq!(
+            PairRate::max_input_for_output_cap(&price, max_output).unwrap(),
+            MAX_RATE - 1
+        );
+        let err = PairRate::max_input_for_output_cap(&price, max_output + 1).unwrap_err();
+        assert!(err.contains("exceeds maximum producible output"));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_blend_by_confidence() {
+        let feed = |rate| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                (18, 18),
+            )
+            .unwrap()
+        };
+
+        // 高置信度的 2.0 把结果拉向自己：(1 * 1 + 9 * 2) / 10 = 1.9
+        let blended =
+            PairRate::blend_by_confidence(&[(feed((1, 1)), 1), (feed((1, 2)), 9)]).unwrap();
+        assert_eq!(blended.rate, (10, 19));
+
+        // 权重相同时为算术平均
+        let blended =
+            PairRate::blend_by_confidence(&[(feed((1, 1)), 5), (feed((2, 6)), 5)]).unwrap();
+        assert_eq!(blended.rate, (1, 2));
+
+        // 权重为 0 的报价源被忽略
+        let blended =
+            PairRate::blend_by_confidence(&[(feed((1, 1)), 0), (feed((3, 7)), 2)]).unwrap();
+        assert_eq!(blended.rate, (3, 7));
+
+        assert!(PairRate::blend_by_confidence(&[]).is_err());
+        assert!(PairRate::blend_by_confidence(&[(feed((1, 1)), 0)]).is_err());
+        let other = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_C".to_string()),
+            (1, 1),
+            (18, 18),
+        )
+        .unwrap();
+        assert!(PairRate::blend_by_confidence(&[(feed((1, 1)), 1), (other, 1)]).is_err());
+    }
+
+    #[cfg(feature = "u256")]
+    #[test]
+    fn test_calculate_output_amount_u256() {
+        // 6 位精度 -> 36 位精度，结果超出 u128
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 3),
+            (6, 36),
+        )
+        .unwrap();
+        let input_amount = 1_000_000_000_000u128; // 1_000_000 个 TOKEN_A
+        assert!(PairRate::calculate_output_amount(&price, input_amount).is_err());
+
+        let output = PairRate::calculate_output_amount_u256(&price, input_amount).unwrap();
+        let expected = U256::from(3_000_000_000_000u128) * U256::from(10u128.pow(30));
+        assert_eq!(output, expected);
+        assert!(output > U256::from(u128::MAX));
+
+        // u128 范围内与普通计算一致
+        let small = PairRate::calculate_output_amount_u256(&price, 1).unwrap();
+        assert_eq!(
+            small,
+            U256::from(PairRate::calculate_output_amount(&price, 1).unwrap())
+        );
+
+        assert!(PairRate::calculate_output_amount_u256(&price, 0).is_err());
+    }
+
+    #[test]
+    fn test_rate_convergents() {
+        // 3.141593
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1_000_000, 3_141_593),
+            (18, 18),
+        )
+        .unwrap();
+
+        assert_eq!(
+            price.rate_convergents(1_000),
+            vec![(1, 3), (7, 22), (113, 355)]
+        );
+        assert_eq!(
+            *price.rate_convergents(u128::MAX).last().unwrap(),
+            (1_000_000, 3_141_593)
+        );
+
+        // 小于 1 的汇率跳过分子为 0 的渐近分数：0.3 = 1 / (3 + 1/3)
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 3),
+            (18, 18),
+        )
+        .unwrap();
+        assert_eq!(price.rate_convergents(100), vec![(3, 1), (10, 3)]);
+    }
+
+    #[test]
+    fn test_calculate_output_amount_output_tick() {
+        // 1.9 汇率，输出精度 2，tick 为 0.05 个代币
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (2, 2),
+        )
+        .unwrap();
+
+        // 1.23 * 1.9 = 2.337 -> 233 -> 230 + 3
+        assert_eq!(
+            PairRate::calculate_output_amount_output_tick(&price, 123, 5).unwrap(),
+            (230, 3)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_output_tick(&price, 100, 10).unwrap(),
+            (190, 0)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_output_tick(&price, 10, 100).unwrap(),
+            (0, 19)
+        );
+        assert!(PairRate::calculate_output_amount_output_tick(&price, 123, 0).is_err());
+    }
+
+    #[test]
+    fn test_output_elasticity() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 大额输入时接近线性
+        let elasticity = price.output_elasticity(10u128.pow(18)).unwrap();
+        assert!((elasticity - 1.0).abs() < 1e-9);
+
+        // 输入 10 时输出 19，上调 1 个基点后仍截断为 19
+        assert_eq!(price.output_elasticity(10).unwrap(), 0.0);
+
+        assert!(price.output_elasticity(0).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_log_price() {
+        let token_pair = || ("TOKEN_A".to_string(), "TOKEN_B".to_string());
+
+        let price = PairRate::from_log_price(token_pair(), 2.0, 10, (18, 6)).unwrap();
+        assert_eq!(price.rate, (1, 100));
+        assert_eq!(price.decimals, (18, 6));
+
+        // 2^-3 = 0.125
+        let price = PairRate::from_log_price(token_pair(), -3.0, 2, (18, 18)).unwrap();
+        assert_eq!(price.rate, (8, 1));
+
+        // 10^1.5 ≈ 31.6227766016838
+        let price = PairRate::from_log_price(token_pair(), 1.5, 10, (18, 18)).unwrap();
+        assert!((price.get_human_readable_rate() - 10f64.powf(1.5)).abs() < 1e-12);
+
+        assert!(PairRate::from_log_price(token_pair(), f64::NAN, 10, (18, 18)).is_err());
+        assert!(PairRate::from_log_price(token_pair(), 40.0, 10, (18, 18)).is_err());
+        assert!(PairRate::from_log_price(token_pair(), -30.0, 10, (18, 18)).is_err());
+        assert!(PairRate::from_log_price(token_pair(), 1.0, 1, (18, 18)).is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_from_parts() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (6, 6),
+        )
+        .unwrap();
+
+        // 2.5 * 1.9 = 4.75
+        assert_eq!(
+            PairRate::calculate_output_from_parts(&price, 2, 500_000).unwrap(),
+            4_750_000
+        );
+        assert_eq!(
+            PairRate::calculate_output_from_parts(&price, 0, 10).unwrap(),
+            19
+        );
+        assert_eq!(
+            PairRate::calculate_output_from_parts(&price, 3, 0).unwrap(),
+            PairRate::calculate_output_amount(&price, 3_000_000).unwrap()
+        );
+
+        assert!(PairRate::calculate_output_from_parts(&price, 1, 1_000_000).is_err());
+        assert!(PairRate::calculate_output_from_parts(&price, u128::MAX, 0).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_apr() {
+        let token_pair = || ("TOKEN_A".to_string(), "TOKEN_B".to_string());
+
+        // 年化 10% 持有半年：1.05
+        let price = PairRate::from_apr(token_pair(), 1_000, 15_768_000, (18, 18)).unwrap();
+        assert_eq!(price.rate, (20, 21));
+        assert!((price.get_human_readable_rate() - 1.05).abs() < 1e-12);
+
+        let price = PairRate::from_apr(token_pair(), 1_000, 0, (18, 18)).unwrap();
+        assert_eq!(price.rate, (1, 1));
+    }
+
+    #[test]
+    fn test_round_trip_within() {
+        let clean = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 18),
+        )
+        .unwrap();
+        assert!(clean.round_trip_within(1_000, 0).unwrap());
+
+        // 10 -> 3 -> 9，损失 10%
+        let lossy = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (18, 18),
+        )
+        .unwrap();
+        assert!(!lossy.round_trip_within(10, 50).unwrap());
+        assert!(lossy.round_trip_within(10, 1_000).unwrap());
+
+        assert!(lossy.round_trip_within(2, 50).is_err());
+    }
+
+    #[test]
+    fn test_route_output_bounded_loss() {
+        let hop = |from: &str, to: &str, rate| {
+            PairRate::new((from.to_string(), to.to_string()), rate, (18, 18)).unwrap()
+        };
+
+        let clean = [hop("A", "B", (1, 2)), hop("B", "C", (1, 2))];
+        assert_eq!(
+            PairRate::route_output_bounded_loss(&clean, 100, 0).unwrap(),
+            400
+        );
+
+        // 100 -> 33 -> 11 -> 3，最后一跳 11/3 截断为 3，累计损失约 18%
+        let lossy = [
+            hop("A", "B", (3, 1)),
+            hop("B", "C", (3, 1)),
+            hop("C", "D", (3, 1)),
+        ];
+        assert!(PairRate::route_output_bounded_loss(&lossy, 100, 100).is_err());
+        assert_eq!(
+            PairRate::route_output_bounded_loss(&lossy, 100, 2_000).unwrap(),
+            3
+        );
+
+        // 精度 6 -> 8 的一跳：精确输出 333.33，实际输出 300，损失 10%
+        let scale_up = [PairRate::new(("A".to_string(), "B".to_string()), (3, 1), (6, 8)).unwrap()];
+        assert!(PairRate::route_output_bounded_loss(&scale_up, 10, 900).is_err());
+        assert_eq!(
+            PairRate::route_output_bounded_loss(&scale_up, 10, 1_100).unwrap(),
+            300
+        );
+
+        let disconnected = [hop("A", "B", (1, 2)), hop("C", "D", (1, 2))];
+        assert!(PairRate::route_output_bounded_loss(&disconnected, 100, 0).is_err());
+        assert!(PairRate::route_output_bounded_loss(&[], 100, 0).is_err());
+    }
+
+    #[test]
+    fn test_marginal_price_cpmm() {
+        assert_eq!(PairRate::marginal_price_cpmm(5_000, 5_000).unwrap(), (1, 1));
+        assert_eq!(PairRate::marginal_price_cpmm(1_000, 2_500).unwrap(), (2, 5));
+        assert!(PairRate::marginal_price_cpmm(0, 1).is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_amount_midpoint() {
+        // 精度 6 -> 8：10 / 3 = 3 余 1，向下取整 300，向上取整 400
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (6, 8),
+        )
+        .unwrap();
+        assert_eq!(PairRate::calculate_output_amount(&price, 10).unwrap(), 300);
+        assert_eq!(
+            PairRate::calculate_output_amount_midpoint(&price, 10).unwrap(),
+            350
+        );
+
+        // 除尽时与向下取整一致
+        assert_eq!(
+            PairRate::calculate_output_amount_midpoint(&price, 9).unwrap(),
+            300
+        );
+
+        // 精度相同：向上取整只多 1，平均值向下取整
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (6, 6),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_midpoint(&price, 10).unwrap(),
+            3
+        );
+        assert!(PairRate::calculate_output_amount_midpoint(&price, 2).is_err());
+    }
+
+    #[test]
+    fn test_is_power_of_ten_rate() {
+        let pair = |rate, decimals| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                decimals,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(pair((1, 10), (18, 18)).is_power_of_ten_rate(), Some(1));
+        assert_eq!(pair((300, 3), (6, 6)).is_power_of_ten_rate(), Some(-2));
+        // 汇率 1:1，精度 18 -> 6 使人类可读汇率为 10^12
+        assert_eq!(pair((1, 1), (18, 6)).is_power_of_ten_rate(), Some(12));
+        assert_eq!(pair((1, 1), (6, 6)).is_power_of_ten_rate(), Some(0));
+        assert_eq!(pair((10, 19), (18, 18)).is_power_of_ten_rate(), None);
+        assert_eq!(pair((1, 20), (18, 18)).is_power_of_ten_rate(), None);
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let pair = |rate, decimals| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                decimals,
+            )
+            .unwrap()
+        };
+        let output = |price: &PairRate, input, mode| {
+            PairRate::calculate_output_amount_with_rounding(price, input, mode).unwrap()
+        };
+
+        // 汇率乘除步骤：5 / 2 = 2.5，7 / 2 = 3.5
+        let price = pair((2, 1), (6, 6));
+        assert_eq!(output(&price, 5, RoundingMode::Truncate), 2);
+        assert_eq!(output(&price, 5, RoundingMode::Floor), 2);
+        assert_eq!(output(&price, 5, RoundingMode::Ceil), 3);
+        assert_eq!(output(&price, 5, RoundingMode::HalfUp), 3);
+        assert_eq!(output(&price, 5, RoundingMode::HalfEven), 2);
+        assert_eq!(output(&price, 7, RoundingMode::HalfEven), 4);
+
+        // 精度调整步骤：精度 8 -> 6，250 -> 2.5
+        let price = pair((1, 1), (8, 6));
+        assert_eq!(output(&price, 250, RoundingMode::Truncate), 2);
+        assert_eq!(output(&price, 250, RoundingMode::Ceil), 3);
+        assert_eq!(output(&price, 250, RoundingMode::HalfUp), 3);
+        assert_eq!(output(&price, 250, RoundingMode::HalfEven), 2);
+        assert_eq!(output(&price, 249, RoundingMode::HalfUp), 2);
+        assert_eq!(
+            output(&price, 250, RoundingMode::default()),
+            PairRate::calculate_output_amount(&price, 250).unwrap()
+        );
+
+        // 反算输入时向上取整，保证输入足以换出期望的输出
+        let price = pair((1, 3), (6, 6));
+        let input =
+            PairRate::calculate_input_amount_with_rounding(&price, 5, RoundingMode::Ceil).unwrap();
+        assert_eq!(input, 2);
+        assert!(PairRate::calculate_output_amount(&price, input).unwrap() >= 5);
+        assert_eq!(PairRate::calculate_input_amount(&price, 5).unwrap(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_q64_64_round_trip() {
+        let token_pair = || ("TOKEN_A".to_string(), "TOKEN_B".to_string());
+
+        // 1.5 和 0.25 可精确表示
+        let price = PairRate::from_q64_64(token_pair(), 3 << 63, (18, 6)).unwrap();
+        assert_eq!(price.rate, (2, 3));
+        assert_eq!(price.decimals, (18, 6));
+        assert_eq!(price.to_q64_64().unwrap(), 3 << 63);
+
+        let price = PairRate::new(token_pair(), (4, 1), (18, 18)).unwrap();
+        let q = price.to_q64_64().unwrap();
+        assert_eq!(q, 1 << 62);
+        assert_eq!(
+            PairRate::from_q64_64(token_pair(), q, (18, 18)).unwrap(),
+            price
+        );
+
+        // 1/3 截断到 2^-64 精度，再次转换结果不变
+        let price = PairRate::new(token_pair(), (3, 1), (18, 18)).unwrap();
+        let q = price.to_q64_64().unwrap();
+        let restored = PairRate::from_q64_64(token_pair(), q, (18, 18)).unwrap();
+        assert_eq!(restored.to_q64_64().unwrap(), q);
+        assert!((restored.get_human_readable_rate() - 1.0 / 3.0).abs() < 1e-18);
+
+        assert!(PairRate::from_q64_64(token_pair(), 0, (18, 18)).is_err());
+        let price = PairRate::new(token_pair(), (1, 1 << 70), (18, 18)).unwrap();
+        assert!(price.to_q64_64().is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_amount_with_remainder() {
+        let pair = |rate, decimals| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                decimals,
+            )
+            .unwrap()
+        };
+
+        // 精度相同：10 * 2 / 3 = 6 余 2（除数为 rate.0 = 3）
+        let price = pair((3, 2), (6, 6));
+        assert_eq!(
+            PairRate::calculate_output_amount_with_remainder(&price, 10).unwrap(),
+            (6, 2)
+        );
+
+        // 精度 18 -> 6：1.9 * 1_234_567_890_123_456_789 = 2_345_678_991_234_567_899.1，
+        // 最后一步除以 10^12 舍去 991_234_567_899（汇率除法的余数不计入）
+        let price = pair((10, 19), (18, 6));
+        let input = 1_234_567_890_123_456_789;
+        assert_eq!(
+            PairRate::calculate_output_amount_with_remainder(&price, input).unwrap(),
+            (2_345_678, 991_234_567_899)
+        );
+
+        // 精度 6 -> 8：最后一步除法是 10 / 3 = 3 余 1，再放大为 300
+        let price = pair((3, 1), (6, 8));
+        assert_eq!(
+            PairRate::calculate_output_amount_with_remainder(&price, 10).unwrap(),
+            (300, 1)
+        );
+
+        // 除尽时余数为 0
+        let price = pair((1, 2), (6, 8));
+        assert_eq!(
+            PairRate::calculate_output_amount_with_remainder(&price, 5).unwrap(),
+            (1_000, 0)
+        );
+
+        assert_eq!(
+            PairRate::calculate_output_amount_with_remainder(&price, 0),
+            Err(PairRateError::ZeroInputAmount)
+        );
+    }
+
+    #[test]
+    fn test_calculate_output_amount_with_loss() {
+        let pair = |rate, decimals| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                decimals,
+            )
+            .unwrap()
+        };
+
+        // 精度相同：10 * 2 / 3 = 6.666...，损失 2/3 个最小单位
+        let price = pair((3, 2), (6, 6));
+        assert_eq!(
+            PairRate::calculate_output_amount_with_loss(&price, 10).unwrap(),
+            (6, (0, 12_297_829_382_473_034_410))
+        );
+
+        // 精度 18 -> 6：精确输出 2_345_678.9912345678991，
+        // 精度调整舍去 0.991234567899，汇率除法的余数 0.1 再贡献 10^-13
+        let price = pair((10, 19), (18, 6));
+        let input = 1_234_567_890_123_456_789;
+        assert_eq!(
+            PairRate::calculate_output_amount_with_loss(&price, input).unwrap(),
+            (2_345_678, (0, 18_285_050_391_048_771_076))
+        );
+
+        // 精度 6 -> 8：先截断 10 / 3 = 3 再放大为 300，精确输出 333.33...，损失 33.33...
+        let price = pair((3, 1), (6, 8));
+        assert_eq!(
+            PairRate::calculate_output_amount_with_loss(&price, 10).unwrap(),
+            (300, (33, 6_148_914_691_236_517_205))
+        );
+
+        // 除尽时损失为 0
+        let price = pair((1, 2), (6, 8));
+        assert_eq!(
+            PairRate::calculate_output_amount_with_loss(&price, 5).unwrap(),
+            (1_000, (0, 0))
+        );
+
+        assert_eq!(
+            PairRate::calculate_output_amount_with_loss(&price, 0),
+            Err(PairRateError::ZeroInputAmount)
+        );
+    }
+
+    #[test]
+    fn test_calculate_input_full() {
+        let pair = |rate, decimals| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                decimals,
+            )
+            .unwrap()
+        };
+
+        for price in [
+            pair((10, 19), (18, 18)),
+            pair((3, 7), (18, 6)),
+            pair((7, 3), (6, 18)),
+        ] {
+            for desired in [1u128, 999, 1_000_000, 123_456_789_012] {
+                let input = PairRate::calculate_input_full(&price, desired, 30, 50).unwrap();
+                let net = PairRate::calculate_output_full(&price, input, 30, 50).unwrap();
+                assert!(net >= desired, "rate {:?}, desired {}", price.rate, desired);
+
+                // 少一个最小单位就不够
+                if input > 1 {
+                    let short = PairRate::calculate_output_full(&price, input - 1, 30, 50);
+                    assert!(short.map_or(true, |net| net < desired));
+                }
+            }
+        }
+
+        // 1:1 汇率，手续费 30 基点、滑点 50 基点：10_000 * 0.995 * 0.997 = 9920.15
+        let price = pair((1, 1), (6, 6));
+        assert_eq!(
+            PairRate::calculate_output_full(&price, 10_000, 30, 50).unwrap(),
+            9_920
+        );
+        assert_eq!(
+            PairRate::calculate_input_full(&price, 9_920, 30, 50).unwrap(),
+            10_000
+        );
+
+        assert_eq!(
+            PairRate::calculate_input_full(&price, 100, 10_001, 0),
+            Err("Fee 10001 bps exceeds maximum allowed 10000".to_string())
+        );
+        assert_eq!(
+            PairRate::calculate_output_full(&price, 10_000, 0, 10_001),
+            Err("Slippage 10001 bps exceeds maximum allowed 10000".to_string())
+        );
+        assert_eq!(
+            PairRate::calculate_input_full(&price, 100, 0, 10_001),
+            Err("Slippage 10001 bps exceeds maximum allowed 10000".to_string())
+        );
+        assert!(PairRate::calculate_input_full(&price, 100, 0, 10_000).is_err());
+        assert!(PairRate::calculate_input_full(&price, 100, 10_000, 0).is_err());
+        assert!(PairRate::calculate_input_full(&price, 0, 30, 50).is_err());
+    }
+
+    #[test]
+    fn test_calculate_amount_with_fee() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 18),
+        )
+        .unwrap();
+
+        // 1:1 汇率，30 基点手续费
+        assert_eq!(
+            PairRate::calculate_output_amount_with_fee(&price, 10_000, 30).unwrap(),
+            9_970
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_with_fee(&price, 1_000, 30).unwrap(),
+            997
+        );
+        assert_eq!(
+            PairRate::calculate_input_amount_with_fee(&price, 9_970, 30).unwrap(),
+            10_000
+        );
+
+        // 1000 / 0.997 = 1003.0…，向上取整为 1004
+        let input = PairRate::calculate_input_amount_with_fee(&price, 1_000, 30).unwrap();
+        assert_eq!(input, 1_004);
+        assert!(PairRate::calculate_output_amount_with_fee(&price, input, 30).unwrap() >= 1_000);
+        assert!(PairRate::calculate_output_amount_with_fee(&price, input - 1, 30).unwrap() < 1_000);
+
+        assert_eq!(
+            PairRate::calculate_output_amount_with_fee(&price, 1_000, 10_001),
+            Err(PairRateError::FeeOutOfRange {
+                fee_bps: 10_001,
+                max: 10_000
+            })
+        );
+        assert!(PairRate::calculate_input_amount_with_fee(&price, 1_000, 10_000).is_err());
+        assert_eq!(
+            PairRate::calculate_output_amount_with_fee(&price, 1_000, 0).unwrap(),
+            PairRate::calculate_output_amount(&price, 1_000).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_effective_rate_scientific() {
+        let pair = |rate, decimals| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                decimals,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            pair((10, 19), (18, 18))
+                .effective_rate_scientific(3)
+                .unwrap(),
+            "1.90e0"
+        );
+        assert_eq!(
+            pair((400, 1), (18, 18))
+                .effective_rate_scientific(2)
+                .unwrap(),
+            "2.5e-3"
+        );
+        assert_eq!(
+            pair((3, 2), (6, 6)).effective_rate_scientific(3).unwrap(),
+            "6.67e-1"
+        );
+        // 精度 18 -> 6 使汇率放大 10^12
+        assert_eq!(
+            pair((1, 1), (18, 6)).effective_rate_scientific(3).unwrap(),
+            "1.00e12"
+        );
+        assert_eq!(
+            pair((1, 1), (6, 18)).effective_rate_scientific(1).unwrap(),
+            "1e-12"
+        );
+        // 四舍五入进位到下一个数量级
+        assert_eq!(
+            pair((1, 999_999), (18, 18))
+                .effective_rate_scientific(3)
+                .unwrap(),
+            "1.00e6"
+        );
+        assert_eq!(
+            pair((1, 123_456_789), (18, 18))
+                .effective_rate_scientific(4)
+                .unwrap(),
+            "1.235e8"
+        );
+
+        // 去掉末尾的 0
+        assert_eq!(
+            pair((10, 19), (18, 18))
+                .effective_rate_scientific_trimmed(3)
+                .unwrap(),
+            "1.9e0"
+        );
+        assert_eq!(
+            pair((1, 1), (18, 6))
+                .effective_rate_scientific_trimmed(5)
+                .unwrap(),
+            "1e12"
+        );
+
+        assert!(pair((1, 1), (18, 18)).effective_rate_scientific(0).is_err());
+    }
+
+    #[test]
+    fn test_quantize_to_oracle() {
+        let token_pair = || ("TOKEN_A".to_string(), "TOKEN_B".to_string());
+
+        // 精度 18 -> 6：人类可读汇率为 2/3 * 10^12，报价保留 6 位小数
+        let price = PairRate::new(token_pair(), (3, 2), (18, 6)).unwrap();
+        assert_eq!(price.to_oracle_answer(6).unwrap(), 666_666_666_666_666_667);
+        let quantized = price.quantize_to_oracle(6).unwrap();
+        assert_eq!(
+            quantized,
+            PairRate::from_oracle_answer(
+                token_pair(),
+                price.to_oracle_answer(6).unwrap(),
+                6,
+                (18, 6)
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            quantized.to_oracle_answer(6).unwrap(),
+            666_666_666_666_666_667
+        );
+
+        // 精度相同：1.23456 量化到 2 位小数为 1.23
+        let price = PairRate::new(token_pair(), (100_000, 123_456), (8, 8)).unwrap();
+        let quantized = price.quantize_to_oracle(2).unwrap();
+        assert_eq!(quantized.rate, (100, 123));
+        assert_eq!(quantized.to_oracle_answer(2).unwrap(), 123);
+
+        // 汇率在该精度下舍入为 0
+        let price = PairRate::new(token_pair(), (1_000, 1), (6, 6)).unwrap();
+        assert_eq!(price.to_oracle_answer(2).unwrap(), 0);
+        assert!(price.quantize_to_oracle(2).is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_within_reserve() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 18),
+        )
+        .unwrap();
+
+        assert_eq!(
+            PairRate::calculate_output_within_reserve(&price, 100, 190).unwrap(),
+            190
+        );
+
+        // 1.9 * 105 = 199.5 > 195；输入 103 得 195，输入 104 得 197
+        let err = PairRate::calculate_output_within_reserve(&price, 105, 195).unwrap_err();
+        assert_eq!(
+            err,
+            "Output amount 199 exceeds reserve 195, max fillable input 103"
+        );
+        assert_eq!(
+            PairRate::calculate_output_within_reserve(&price, 103, 195).unwrap(),
+            195
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+
+        let inverse = price.inverse();
+        assert_eq!(
+            inverse.token_pair,
+            ("TOKEN_B".to_string(), "TOKEN_A".to_string())
+        );
+        assert_eq!(inverse.rate, (19, 10));
+        assert_eq!(inverse.decimals, (6, 18));
+        assert_eq!(inverse.inverse(), price);
+
+        for amount in [19u128, 1_000_000, 123_456_789] {
+            assert_eq!(
+                PairRate::calculate_output_amount(&inverse, amount),
+                PairRate::calculate_input_amount(&price, amount)
+            );
+        }
+
+        // 截断不满足交换律：正向再反向换算会损失数量
+        let output = PairRate::calculate_output_amount(&price, 1_234_567_890_123_456_789).unwrap();
+        let back = PairRate::calculate_output_amount(&inverse, output).unwrap();
+        assert!(back < 1_234_567_890_123_456_789);
+    }
+
+    #[test]
+    fn test_detect_wrong_direction() {
+        let price = PairRate::new(
+            ("WETH".to_string(), "USDC".to_string()),
+            (1, 3_000),
+            (18, 6),
+        )
+        .unwrap();
+
+        // 0.5 WETH 与 1500 USDC
+        assert_eq!(
+            price.detect_wrong_direction(500_000_000_000_000_000),
+            Ok(DirectionHint::LikelyInput)
+        );
+        assert_eq!(
+            price.detect_wrong_direction(1_500_000_000),
+            Ok(DirectionHint::LikelyOutput)
+        );
+        // 10^12 与两侧精度距离相同
+        assert_eq!(
+            price.detect_wrong_direction(1_000_000_000_000),
+            Ok(DirectionHint::Ambiguous)
+        );
+
+        let same = PairRate::new(("A".to_string(), "B".to_string()), (1, 1), (18, 18)).unwrap();
+        assert_eq!(
+            same.detect_wrong_direction(42),
+            Ok(DirectionHint::Ambiguous)
+        );
+        assert!(price.detect_wrong_direction(0).is_err());
+    }
+
+    #[test]
+    fn test_calculate_output_amounts() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+        let inputs = [
+            1_000_000_000_000_000_000,
+            2_000_000_000_000,
+            10_000_000_000_000,
+        ];
+
+        let outputs = PairRate::calculate_output_amounts(&price, &inputs).unwrap();
+        let expected: Vec<u128> = inputs
+            .iter()
+            .map(|&input| PairRate::calculate_output_amount(&price, input).unwrap())
+            .collect();
+        assert_eq!(outputs, expected);
+        assert_eq!(outputs, vec![1_900_000, 3, 19]);
+        assert_eq!(
+            PairRate::calculate_output_amounts(&price, &[]).unwrap(),
+            vec![]
+        );
+
+        // 第一个出错的输入带下标返回
+        let err =
+            PairRate::calculate_output_amounts(&price, &[1_000_000_000_000, 0, 1]).unwrap_err();
+        assert_eq!(
+            err,
+            PairRateError::AtIndex {
+                index: 1,
+                error: Box::new(PairRateError::ZeroInputAmount),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Input 1: Input amount must be greater than 0"
+        );
+
+        let partial =
+            PairRate::calculate_output_amounts_partial(&price, &[1_000_000_000_000, 0, 1]);
+        assert_eq!(partial[0], Ok(1));
+        assert_eq!(partial[1], Err(PairRateError::ZeroInputAmount));
+        assert_eq!(partial[2], Err(PairRateError::ZeroCalculatedOutput));
+
+        // 交易对无效时只返回校验错误
+        let invalid = PairRate {
+            rate: (0, 1),
+            ..price
+        };
+        assert_eq!(
+            PairRate::calculate_output_amounts(&invalid, &inputs),
+            Err(PairRateError::ZeroRate)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amounts_partial(&invalid, &[1, 2]),
+            vec![Err(PairRateError::ZeroRate), Err(PairRateError::ZeroRate)]
+        );
+    }
+
+    #[test]
+    fn test_fee_inclusive_pair() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+
+        let inclusive = price.fee_inclusive_pair(30).unwrap();
+        assert_eq!(inclusive.rate, (10_000, 18_943));
+        for input in [
+            1_000_000_000_000_000_000,
+            10_000_000_000_000,
+            123_450_000_000_000_000,
+            7_000_000_000_000_000_000_000,
+        ] {
+            assert_eq!(
+                PairRate::calculate_output_amount(&inclusive, input),
+                PairRate::calculate_output_amount_with_fee(&price, input, 30)
+            );
+        }
+
+        // 扣费前输出被截断时，最多多出 1 个最小单位
+        let input = 1_234_567_890_123_456_789;
+        let with_fee = PairRate::calculate_output_amount_with_fee(&price, input, 30).unwrap();
+        let direct = PairRate::calculate_output_amount(&inclusive, input).unwrap();
+        assert!(direct - with_fee <= 1);
+
+        assert_eq!(price.fee_inclusive_pair(0).unwrap().rate, (10, 19));
+        assert!(price.fee_inclusive_pair(MAX_FEE_BPS).is_err());
+    }
+
+    #[test]
+    fn test_route_output_amount() {
+        let a_b = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 6),
+        )
+        .unwrap();
+        let b_c = PairRate::new(
+            ("TOKEN_B".to_string(), "TOKEN_C".to_string()),
+            (4, 3),
+            (6, 9),
+        )
+        .unwrap();
+        let c_d = PairRate::new(
+            ("TOKEN_C".to_string(), "TOKEN_D".to_string()),
+            (1, 10),
+            (9, 18),
+        )
+        .unwrap();
+        let hops = [&a_b, &b_c, &c_d];
+
+        // 1 A -> 2 B -> 1.5 C -> 15 D
+        let output = PairRate::route_output_amount(&hops, 1_000_000_000_000_000_000).unwrap();
+        assert_eq!(output, 15_000_000_000_000_000_000);
+        assert_eq!(
+            PairRate::route_input_amount(&hops, output).unwrap(),
+            1_000_000_000_000_000_000
+        );
+
+        assert_eq!(
+            PairRate::route_output_amount(&[&a_b, &c_d], 1),
+            Err(PairRateError::RouteMismatch { at_index: 0 })
+        );
+        assert_eq!(
+            PairRate::route_input_amount(&[&a_b, &b_c, &a_b], 1),
+            Err(PairRateError::RouteMismatch { at_index: 1 })
+        );
+        assert_eq!(
+            PairRate::route_output_amount(&[], 1),
+            Err(PairRateError::EmptyRoute)
+        );
+    }
+
+    #[test]
+    fn test_calculate_output_sqrt() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (6, 6),
+        )
+        .unwrap();
+        // sqrt(1_000_000) = 1000，乘以 2
+        assert_eq!(
+            PairRate::calculate_output_sqrt(&price, 1_000_000).unwrap(),
+            2_000
+        );
+        // sqrt(10) 向下取整为 3
+        assert_eq!(PairRate::calculate_output_sqrt(&price, 10).unwrap(), 6);
+        assert_eq!(PairRate::calculate_output_sqrt(&price, 1).unwrap(), 2);
+        assert_eq!(
+            PairRate::calculate_output_sqrt(&price, u128::MAX).unwrap(),
+            2 * u64::MAX as u128
+        );
+        assert!(PairRate::calculate_output_sqrt(&price, 0).is_err());
+
+        // sqrt(10^36) = 10^18，乘以 2 后从 18 位精度调整到 6 位
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 6),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_sqrt(&price, 10u128.pow(36)).unwrap(),
+            2_000_000
+        );
+    }
+
+    #[test]
+    fn test_price_impact_bps() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (6, 6),
+        )
+        .unwrap();
+
+        // 4 / 3 = 1.333...，截断为 1，偏离 25%
+        assert_eq!(PairRate::price_impact_bps(&price, 4).unwrap(), 2_500);
+        assert_eq!(PairRate::price_impact_bps(&price, 3).unwrap(), 0);
+        assert_eq!(PairRate::price_impact_bps(&price, 3_001).unwrap(), 3);
+        assert_eq!(
+            PairRate::price_impact_bps(&price, 2),
+            Err(PairRateError::ZeroCalculatedOutput)
+        );
+
+        // 大额输入的偏离接近 0
+        assert_eq!(
+            PairRate::price_impact_bps(&price, 1_000_000_000_000).unwrap(),
+            0
+        );
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::price_impact_bps(&price, 1_234_567_890_123_456_789).unwrap(),
+            0
+        );
+        assert_eq!(
+            PairRate::price_impact_bps(&price, u128::MAX / 19).unwrap(),
+            0
+        );
+
+        // 精度提高：先截断再放大，10 / 3 * 100 = 333.33 实际输出 300，偏离 10%
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (6, 8),
+        )
+        .unwrap();
+        assert_eq!(PairRate::price_impact_bps(&price, 10).unwrap(), 1_000);
+        assert_eq!(PairRate::price_impact_bps(&price, 1_000).unwrap(), 10);
+        assert_eq!(PairRate::price_impact_bps(&price, 3_000_001).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_batch_dust() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (6, 6),
+        )
+        .unwrap();
+        // 每个输入损失 1/3 或 2/3 个输出最小单位
+        assert_eq!(price.batch_dust(&[1, 1, 1]).unwrap(), 1);
+        assert_eq!(price.batch_dust(&[2, 4, 5, 3]).unwrap(), 2);
+        assert_eq!(price.batch_dust(&[3, 6, 9]).unwrap(), 0);
+        assert_eq!(price.batch_dust(&[]).unwrap(), 0);
+
+        // 输出精度较低：每个输入丢弃 0.5 个输出最小单位
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 1),
+            (18, 6),
+        )
+        .unwrap();
+        let inputs = [1_500_000_000_000; 6];
+        assert_eq!(price.batch_dust(&inputs).unwrap(), 3);
+
+        // 输出精度较高：先截断再放大，10 / 3 * 100 = 333.33 实际输出 300
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (6, 8),
+        )
+        .unwrap();
+        assert_eq!(price.batch_dust(&[10]).unwrap(), 33);
+        assert_eq!(price.batch_dust(&[10, 10, 10]).unwrap(), 100);
+        assert_eq!(price.batch_dust(&[1]).unwrap(), 33);
+
+        let invalid = PairRate {
+            rate: (0, 1),
+            ..price
+        };
+        assert!(invalid.batch_dust(&[1]).is_err());
     }
 
     #[test]
-    fn test_calculate_input_amount() {
+    fn test_decimal_diff_boundary() {
+        // 上限包含：差值 32 允许，33 拒绝
+        assert_eq!(PairRate::adjust_decimals(3, 0, 32), Ok(3 * 10u128.pow(32)));
+        assert_eq!(PairRate::adjust_decimals(10u128.pow(33), 33, 1), Ok(10));
+        assert_eq!(
+            PairRate::adjust_decimals(1, 0, 33),
+            Err(PairRateError::DecimalDiffTooLarge { diff: 33, max: 32 })
+        );
+        assert_eq!(
+            PairRate::adjust_decimals(u128::MAX, 0, 32),
+            Err(PairRateError::Overflow {
+                stage: OverflowStage::DecimalScaleUp,
+                operand_bits: (128, 107),
+            })
+        );
+
+        // 严格版本在上限处也拒绝
+        assert_eq!(
+            PairRate::adjust_decimals_strict(3, 0, 32),
+            Err(PairRateError::DecimalDiffTooLarge { diff: 32, max: 31 })
+        );
+        assert_eq!(
+            PairRate::adjust_decimals_strict(10u128.pow(33), 33, 1),
+            Err(PairRateError::DecimalDiffTooLarge { diff: 32, max: 31 })
+        );
+        assert_eq!(
+            PairRate::adjust_decimals_strict(3, 0, 31),
+            Ok(3 * 10u128.pow(31))
+        );
+        assert_eq!(PairRate::adjust_decimals_strict(1_500, 34, 31), Ok(1));
+
+        // 交易对层面同样包含上限
         let price = PairRate::new(
             ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (1, 2),
-            (24, 18),
+            (1, 1),
+            (0, 32),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, 2),
+            Ok(2 * 10u128.pow(32))
+        );
+        let price = PairRate {
+            decimals: (0, 33),
+            ..price
+        };
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, 2),
+            Err(PairRateError::DecimalDiffTooLarge { diff: 33, max: 32 })
+        );
+    }
+
+    #[test]
+    fn test_display_from_str() {
+        let price: PairRate = "TOKEN_A/TOKEN_B=2.5@18:6".parse().unwrap();
+        assert_eq!(
+            price.token_pair,
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string())
+        );
+        assert_eq!(price.rate, (400_000_000_000, 1));
+        assert_eq!(price.decimals, (18, 6));
+        #[cfg(feature = "std")]
+        assert_eq!(price.get_human_readable_rate(), 2.5);
+        assert_eq!(price.to_string(), "TOKEN_A/TOKEN_B=2.5@18:6");
+        assert_eq!(price.to_string().parse::<PairRate>().unwrap(), price);
+
+        // 精度不同时与 from_human_readable 含义一致，并可往返
+        let price: PairRate = "A/B=2.5@6:9".parse().unwrap();
+        assert_eq!(
+            price,
+            PairRate::from_human_readable(("A".to_string(), "B".to_string()), "2.5", (6, 9))
+                .unwrap()
+        );
+        assert_eq!(price.rate, (1, 2_500));
+        #[cfg(feature = "std")]
+        assert_eq!(price.get_human_readable_rate(), 2.5);
+        assert_eq!(price.to_string(), "A/B=2.5@6:9");
+        assert_eq!(price.to_string().parse::<PairRate>().unwrap(), price);
+        let price = PairRate::new(("A".to_string(), "B".to_string()), (4, 1), (6, 18)).unwrap();
+        assert_eq!(price.to_string(), "A/B=0.00000000000025@6:18");
+        assert_eq!(price.to_string().parse::<PairRate>().unwrap(), price);
+
+        assert_eq!("A/B=3@6:6".parse::<PairRate>().unwrap().rate, (1, 3));
+        assert_eq!("A/B=0.0125@6:6".parse::<PairRate>().unwrap().rate, (80, 1));
+        assert_eq!("A/B=.5@6:6".parse::<PairRate>().unwrap().rate, (2, 1));
+
+        // 无限小数保留 18 位，往返后近似原汇率且精度不变
+        let third = PairRate::new(("A".to_string(), "B".to_string()), (3, 1), (18, 6)).unwrap();
+        assert_eq!(
+            third.to_string(),
+            "A/B=333333333333.333333333333333333@18:6"
+        );
+        let parsed: PairRate = third.to_string().parse().unwrap();
+        assert_eq!(parsed.decimals, (18, 6));
+        assert_eq!(
+            parsed.rate,
+            (
+                1_000_000_000_000_000_000_000_000_000_000,
+                333_333_333_333_333_333_333_333_333_333
+            )
+        );
+
+        for malformed in [
+            "",
+            "A/B",
+            "A=2@6:6",
+            "A/B=2@6",
+            "/B=2@6:6",
+            "A/B=2.5.1@6:6",
+            "A/B=-2@6:6",
+            "A/B=@6:6",
+            "A/B=2@6:x",
+            "A/B=2@300:6",
+        ] {
+            assert_eq!(
+                malformed.parse::<PairRate>(),
+                Err(PairRateError::InvalidFormat),
+                "{}",
+                malformed
+            );
+        }
+        assert_eq!(
+            "A/B=2@39:6".parse::<PairRate>(),
+            Err(PairRateError::InputDecimalsExceedMax { value: 39, max: 38 })
+        );
+        assert_eq!(
+            "A/B=0@6:6".parse::<PairRate>(),
+            Err(PairRateError::ZeroRate)
+        );
+    }
+
+    #[test]
+    fn test_format_output_localized() {
+        // 美式
+        assert_eq!(
+            PairRate::format_output_localized(1_234_567_890_000, 6, ',', '.'),
+            "1,234,567.890000"
+        );
+        assert_eq!(
+            PairRate::format_output_localized(123, 6, ',', '.'),
+            "0.000123"
+        );
+        assert_eq!(
+            PairRate::format_output_localized(999_999, 6, ',', '.'),
+            "0.999999"
+        );
+        assert_eq!(PairRate::format_output_localized(0, 2, ',', '.'), "0.00");
+
+        // 欧式
+        assert_eq!(
+            PairRate::format_output_localized(1_234_567_890_000, 6, '.', ','),
+            "1.234.567,890000"
+        );
+        assert_eq!(PairRate::format_output_localized(5, 3, '.', ','), "0,005");
+        assert_eq!(
+            PairRate::format_output_localized(123_456, 0, ' ', ','),
+            "123 456"
+        );
+        assert_eq!(
+            PairRate::format_output_localized(1_000, 0, '.', ','),
+            "1.000"
+        );
+        assert_eq!(PairRate::format_output_localized(100, 0, '.', ','), "100");
+        assert_eq!(
+            PairRate::format_output_localized(u128::MAX, 38, ',', '.'),
+            "3.40282366920938463463374607431768211455"
+        );
+    }
+
+    #[test]
+    fn test_scale_rate() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+
+        // 扣 0.3% 手续费，再除以 1.05 的 rebase 系数
+        let scaled = price.scale_rate((9_970, 10_000), (105, 100)).unwrap();
+        assert_eq!(scaled.rate, (10_500, 18_943));
+        assert_eq!(scaled.decimals, price.decimals);
+        let input = 1_000_000_000_000_000_000;
+        assert_eq!(
+            PairRate::calculate_output_amount(&scaled, input).unwrap(),
+            1_804_095
+        );
+
+        // 乘除相同的因子时汇率不变
+        assert_eq!(price.scale_rate((3, 7), (3, 7)).unwrap().rate, (10, 19));
+        assert_eq!(price.scale_rate((2, 1), (1, 1)).unwrap().rate, (5, 19));
+
+        assert!(price.scale_rate((0, 1), (1, 1)).is_err());
+        assert!(price.scale_rate((MAX_RATE, 1), (1, 1)).is_err());
+    }
+
+    #[test]
+    fn test_checked_min_max() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+        let input = 1_000_000_000_000_000_000;
+
+        assert_eq!(
+            PairRate::calculate_output_amount_checked_min(&price, input, 1_900_000),
+            Ok(1_900_000)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_checked_min(&price, input, 1_900_001),
+            Err(PairRateError::SlippageExceeded {
+                got: 1_900_000,
+                min: 1_900_001
+            })
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_checked_min(&price, 0, 0),
+            Err(PairRateError::ZeroInputAmount)
+        );
+
+        assert_eq!(
+            PairRate::calculate_input_amount_checked_max(&price, 1_900_000, input),
+            Ok(input)
+        );
+        let err =
+            PairRate::calculate_input_amount_checked_max(&price, 1_900_000, input - 1).unwrap_err();
+        assert_eq!(
+            err,
+            PairRateError::MaxInputExceeded {
+                required: input,
+                max: input - 1
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Required input amount 1000000000000000000 exceeds maximum 999999999999999999"
+        );
+
+        // 截断反算得到 1e18，但 1e18 只能换得 1_900_000，实际需要更多输入
+        assert_eq!(
+            PairRate::calculate_input_amount(&price, 1_900_001),
+            Ok(input)
+        );
+        assert_eq!(
+            PairRate::calculate_input_amount_checked_max(&price, 1_900_001, input),
+            Err(PairRateError::MaxInputExceeded {
+                required: 1_000_000_526_315_789_474,
+                max: input
+            })
+        );
+        let required =
+            PairRate::calculate_input_amount_checked_max(&price, 1_900_001, u128::MAX).unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, required),
+            Ok(1_900_001)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, required - 1),
+            Ok(1_900_000)
+        );
+        assert_eq!(
+            PairRate::calculate_input_amount_checked_max(&price, 0, input),
+            Err(PairRateError::ZeroOutputAmount)
+        );
+    }
+
+    #[test]
+    fn test_hash_map_key() {
+        use std::collections::HashMap;
+
+        let make = || {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                (10, 19),
+                (18, 6),
+            )
+            .unwrap()
+        };
+        let mut sources = HashMap::new();
+        sources.insert(make(), "pool");
+        sources.insert(make().invert(), "reverse pool");
+
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources.get(&make()), Some(&"pool"));
+        let other = PairRate {
+            rate: (10, 20),
+            ..make()
+        };
+        assert_eq!(sources.get(&other), None);
+    }
+
+    #[test]
+    fn test_calculate_output_amount_with_decimals() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+
+        for decimals in [(18, 18), (6, 18), (9, 6), (0, 38)] {
+            let rebuilt = PairRate::new(price.token_pair.clone(), price.rate, decimals).unwrap();
+            for input in [1_000_000_000, 123_456_789_000_000_000] {
+                assert_eq!(
+                    PairRate::calculate_output_amount_with_decimals(&price, input, decimals),
+                    PairRate::calculate_output_amount(&rebuilt, input).map_err(String::from)
+                );
+            }
+        }
+        assert_eq!(price.decimals, (18, 6));
+
+        assert!(PairRate::calculate_output_amount_with_decimals(&price, 1, (39, 6)).is_err());
+        assert!(PairRate::calculate_output_amount_with_decimals(&price, 1, (0, 33)).is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_ord_by_effective_rate() {
+        let make = |rate, decimals| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                decimals,
+            )
+            .unwrap()
+        };
+
+        // 两个汇率只在第 20 位有效数字上不同，f64 无法区分
+        let low = make((10u128.pow(19), 10u128.pow(19) * 2 + 1), (18, 18));
+        let high = make((10u128.pow(19), 10u128.pow(19) * 2 + 2), (18, 18));
+        #[cfg(feature = "std")]
+        assert_eq!(
+            low.get_human_readable_rate(),
+            high.get_human_readable_rate()
+        );
+        assert!(low < high);
+
+        // 比较计入精度差，而不是字段顺序
+        let scaled = make((1, 2), (18, 6));
+        let equivalent = make((1, 2_000_000_000_000), (6, 6));
+        assert_eq!(
+            PairRate::compare_ratios(
+                &scaled.effective_rate_ratio(),
+                &equivalent.effective_rate_ratio()
+            ),
+            core::cmp::Ordering::Equal
+        );
+        assert!(scaled > make((1, 3), (6, 6)));
+
+        let mut quotes = vec![
+            scaled.clone(),
+            make((1, 3), (6, 6)),
+            high.clone(),
+            low.clone(),
+        ];
+        quotes.sort();
+        assert_eq!(quotes, vec![low, high, make((1, 3), (6, 6)), scaled]);
+
+        // 汇率相等但字段不同的交易对不视为相等
+        let reduced = make((1, 2), (18, 18));
+        let unreduced = make((2, 4), (18, 18));
+        assert_ne!(reduced.cmp(&unreduced), core::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_new_strict() {
+        let price = PairRate::new_strict(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+        assert_eq!(price.rate, (10, 19));
+
+        assert_eq!(
+            PairRate::new_strict(("".to_string(), "TOKEN_B".to_string()), (1, 1), (6, 6)),
+            Err(PairRateError::InvalidTokenSymbol("input token is empty"))
+        );
+        assert_eq!(
+            PairRate::new_strict(("TOKEN_A".to_string(), "  ".to_string()), (1, 1), (6, 6)),
+            Err(PairRateError::InvalidTokenSymbol("output token is empty"))
+        );
+        let err = PairRate::new_strict(("USDC".to_string(), "USDC".to_string()), (1, 1), (6, 6))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid token symbol: input and output tokens are identical"
+        );
+
+        // 符号有效时仍执行汇率与精度校验
+        assert_eq!(
+            PairRate::new_strict(("A".to_string(), "B".to_string()), (0, 1), (6, 6)),
+            Err(PairRateError::ZeroRate)
+        );
+        // 宽松构造函数保持不变
+        assert!(PairRate::new(("".to_string(), "".to_string()), (1, 1), (6, 6)).is_ok());
+    }
+
+    #[test]
+    fn test_reachable_rates() {
+        let pairs = [
+            PairRate::new(("A".to_string(), "B".to_string()), (1, 2), (6, 6)).unwrap(),
+            PairRate::new(("B".to_string(), "C".to_string()), (1, 3), (6, 6)).unwrap(),
+            PairRate::new(("C".to_string(), "A".to_string()), (1, 5), (6, 6)).unwrap(),
+            PairRate::new(("D".to_string(), "C".to_string()), (1, 4), (6, 6)).unwrap(),
+        ];
+        let path = |tokens: &[&str]| tokens.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+        let depth_one = PairRate::reachable_rates(&pairs, "A", 1);
+        assert_eq!(
+            depth_one,
+            vec![(path(&["A", "B"]), (1, 2)), (path(&["A", "C"]), (5, 1))]
+        );
+
+        let all = PairRate::reachable_rates(&pairs, "A", 3);
+        assert_eq!(
+            all,
+            vec![
+                (path(&["A", "B"]), (1, 2)),
+                (path(&["A", "B", "C"]), (1, 6)),
+                (path(&["A", "B", "C", "D"]), (2, 3)),
+                (path(&["A", "C"]), (5, 1)),
+                (path(&["A", "C", "B"]), (15, 1)),
+                (path(&["A", "C", "D"]), (20, 1)),
+            ]
+        );
+        // 深度足够时也不会回到起点或重复经过代币
+        assert_eq!(PairRate::reachable_rates(&pairs, "A", 10), all);
+        assert!(PairRate::reachable_rates(&pairs, "A", 0).is_empty());
+        assert!(PairRate::reachable_rates(&pairs, "E", 3).is_empty());
+    }
+
+    #[test]
+    fn test_calculate_output_amount_bounded() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+        let input = 1_000_000_000_000_000_000; // 60 位
+
+        assert_eq!(
+            PairRate::calculate_output_amount_bounded(&price, input, 128),
+            PairRate::calculate_output_amount(&price, input)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_bounded(&price, input, 65),
+            Ok(1_900_000)
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_bounded(&price, input, 64),
+            Err(PairRateError::OperandTooLarge {
+                bits: 65,
+                max_bits: 64
+            })
+        );
+
+        // 超大输入在计算前即被拒绝
+        assert_eq!(
+            PairRate::calculate_output_amount_bounded(&price, u128::MAX / 19, 128),
+            Err(PairRateError::OperandTooLarge {
+                bits: 124 + 5,
+                max_bits: 128
+            })
+        );
+        let wide = PairRate {
+            rate: (1, MAX_RATE),
+            ..price
+        };
+        assert!(matches!(
+            PairRate::calculate_output_amount_bounded(&wide, 3, 128),
+            Err(PairRateError::OperandTooLarge { bits: 129, .. })
+        ));
+    }
+
+    #[test]
+    fn test_calculate_output_meta() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+
+        let output = price
+            .calculate_output_meta(1_000_000_000_000_000_000)
+            .unwrap();
+        assert_eq!(
+            output,
+            AmountWithMeta {
+                value: 1_900_000,
+                token: "TOKEN_B".to_string(),
+                decimals: 6,
+            }
+        );
+        let reverse = price.invert().calculate_output_meta(output.value).unwrap();
+        assert_eq!(reverse.token, "TOKEN_A");
+        assert_eq!(reverse.decimals, 18);
+        assert!(price.calculate_output_meta(0).is_err());
+    }
+
+    #[test]
+    fn test_from_human_readable() {
+        let pair = || ("TOKEN_A".to_string(), "TOKEN_B".to_string());
+
+        assert_eq!(
+            PairRate::from_human_readable(pair(), "2.5", (18, 18))
+                .unwrap()
+                .rate,
+            (2, 5)
+        );
+        assert_eq!(
+            PairRate::from_human_readable(pair(), "0.000001", (6, 6))
+                .unwrap()
+                .rate,
+            (1_000_000, 1)
+        );
+
+        // 与 get_human_readable_rate 相同的约定：2 * 10^6 / 10^18 = 1 / 500_000_000_000
+        let price = PairRate::from_human_readable(pair(), "2", (18, 6)).unwrap();
+        assert_eq!(price.rate, (500_000_000_000, 1));
+        #[cfg(feature = "std")]
+        assert_eq!(price.get_human_readable_rate(), 2.0);
+        let price = PairRate::from_human_readable(pair(), "2", (6, 18)).unwrap();
+        assert_eq!(price.rate, (1, 2_000_000_000_000));
+        #[cfg(feature = "std")]
+        assert_eq!(price.get_human_readable_rate(), 2.0);
+        // 约分后才落入范围的汇率
+        let price = PairRate::from_human_readable(pair(), "0.5", (0, 30)).unwrap();
+        assert_eq!(price.rate, (1, 500_000_000_000_000_000_000_000_000_000));
+        let price = PairRate::from_human_readable(pair(), "1.90", (18, 18)).unwrap();
+        assert_eq!(price.rate, (10, 19));
+        #[cfg(feature = "std")]
+        assert_eq!(price.get_human_readable_rate(), 1.9);
+
+        assert_eq!(
+            PairRate::from_human_readable(pair(), "abc", (6, 6)),
+            Err(PairRateError::InvalidFormat)
+        );
+        assert_eq!(
+            PairRate::from_human_readable(pair(), "0", (6, 6)),
+            Err(PairRateError::ZeroRate)
+        );
+        assert!(PairRate::from_human_readable(pair(), "2.5", (39, 6)).is_err());
+    }
+
+    #[test]
+    fn test_reduced() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (20, 38),
+            (18, 6),
+        )
+        .unwrap();
+        let reduced = price.reduced();
+        assert_eq!(reduced.rate, (10, 19));
+        assert_eq!(reduced.decimals, (18, 6));
+        assert_eq!(reduced.token_pair, price.token_pair);
+        assert_ne!(price, reduced);
+
+        for input in [1_000_000_000_000, 123_456_789_012_345_678_901] {
+            assert_eq!(
+                PairRate::calculate_output_amount(&price, input),
+                PairRate::calculate_output_amount(&reduced, input)
+            );
+        }
+
+        let mut in_place = price.clone();
+        in_place.reduce();
+        assert_eq!(in_place, reduced);
+        assert_eq!(reduced.reduced(), reduced);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_crossover_input() {
+        let make = |rate, decimals| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                decimals,
+            )
+            .unwrap()
+        };
+
+        // 汇率相同：任何输入都相等，返回能换得非零输出的最小输入
+        let a = make((10, 19), (6, 6));
+        let b = make((20, 38), (6, 6));
+        assert_eq!(PairRate::crossover_input(&a, &b).unwrap(), Some(1));
+        let a = make((1, 1), (18, 6));
+        assert_eq!(
+            PairRate::crossover_input(&a, &a.reduced()).unwrap(),
+            Some(1_000_000_000_000)
+        );
+
+        // 0.4 与 0.5：输入 3 时都输出 1
+        let low = make((5, 2), (6, 6));
+        let high = make((2, 1), (6, 6));
+        assert_eq!(PairRate::crossover_input(&low, &high).unwrap(), Some(3));
+        assert_eq!(PairRate::crossover_input(&high, &low).unwrap(), Some(3));
+
+        // 0.45 与 0.9：输入 3 时为 1 对 2，之后差距越来越大
+        let low = make((20, 9), (6, 6));
+        let high = make((10, 9), (6, 6));
+        assert_eq!(PairRate::crossover_input(&low, &high).unwrap(), None);
+
+        // 1.5 与 2.5：每个输入都落在不同的输出值上
+        let low = make((2, 3), (6, 6));
+        let high = make((2, 5), (6, 6));
+        assert_eq!(PairRate::crossover_input(&low, &high).unwrap(), None);
+
+        // 1.9 与 2.1：精度降低时以 10^12 为截断步长，低汇率方首次输出 1 时两者相等
+        let low = make((10, 19), (18, 6));
+        let high = make((10, 21), (18, 6));
+        assert_eq!(
+            PairRate::crossover_input(&low, &high).unwrap(),
+            Some(526_315_789_474)
+        );
+
+        assert!(PairRate::crossover_input(&low, &make((10, 21), (6, 6))).is_err());
+
+        // 汇率极其接近：不能逐个枚举到差距超过截断步长
+        let start = std::time::Instant::now();
+        let low = make(
+            (1_000_000_000_000_000_001, 1_000_000_000_000_000_000),
+            (18, 18),
+        );
+        let high = make((1, 1), (18, 18));
+        assert_eq!(PairRate::crossover_input(&low, &high).unwrap(), None);
+        let high = make(
+            (1_000_000_000_000_000_000, 1_000_000_000_000_000_001),
+            (18, 18),
+        );
+        assert_eq!(PairRate::crossover_input(&low, &high).unwrap(), None);
+        assert_eq!(
+            PairRate::crossover_input(&make((1, 1), (18, 18)), &high).unwrap(),
+            Some(1)
+        );
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        // 与逐个枚举的结果一致
+        let brute_force = |a: &PairRate, b: &PairRate| {
+            (1..=1_000u128).find(|&x| {
+                match (
+                    PairRate::calculate_output_amount(a, x),
+                    PairRate::calculate_output_amount(b, x),
+                ) {
+                    (Ok(a_output), Ok(b_output)) => a_output == b_output,
+                    _ => false,
+                }
+            })
+        };
+        for decimals in [(6, 6), (7, 6), (6, 7)] {
+            for rate_a in (1..=7).flat_map(|i| (1..=7).map(move |j| (i, j))) {
+                for rate_b in [(3, 4), (5, 7), (7, 5), (2, 1), (1, 6)] {
+                    let (a, b) = (make(rate_a, decimals), make(rate_b, decimals));
+                    assert_eq!(
+                        PairRate::crossover_input(&a, &b).unwrap(),
+                        brute_force(&a, &b),
+                        "{:?} vs {:?}",
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_output_amount_toward() {
+        // 10 / 3 = 3.33...
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (3, 1),
+            (6, 6),
         )
         .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 10, 4).unwrap(),
+            4
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 10, 100).unwrap(),
+            4
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 10, 3).unwrap(),
+            3
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 10, 0).unwrap(),
+            3
+        );
+        // 精确结果无需取整
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 9, 100).unwrap(),
+            3
+        );
 
-        // 想要输出2个token（18位精度）
-        let output_amount = 2_000_000_000_000_000_000u128; // 2 tokens with 18 decimals
-        let input = PairRate::calculate_input_amount(&price, output_amount).unwrap();
-        println!("Input amount: {}", input);
-        // 计算步骤：
-        // 1. 基础计算：2_000_000_000_000_000_000 * 1 / 2 = 1_000_000_000_000_000_000
-        // 2. 精度调整：从18位调整到24位，乘以10^6 = 1_000_000_000_000_000_000_000_000
-        assert_eq!(input, 1_000_000_000_000_000_000_000_000);
-    }
+        // 向下取整为 0 时，目标更接近 1 则向上取整
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 1, 1).unwrap(),
+            1
+        );
+        assert!(PairRate::calculate_output_amount_toward(&price, 1, 0).is_err());
 
-    #[test]
-    fn test_same_decimals() {
+        // 精度提高时两个候选相差 10^diff，距离相同取向下
         let price = PairRate::new(
             ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (1, 3),
-            (18, 18),
+            (3, 1),
+            (0, 2),
         )
         .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 10, 360).unwrap(),
+            400
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 10, 350).unwrap(),
+            300
+        );
+        assert_eq!(
+            PairRate::calculate_output_amount_toward(&price, 10, 340).unwrap(),
+            300
+        );
+    }
 
-        // 输入1个token（18位精度）
-        let input_amount = 1_000_000_000_000_000_000u128; // 1 token
-        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
+    #[test]
+    fn test_recommended_scaling() {
+        assert!(PairRate::can_convert(18, 6));
+        assert!(PairRate::can_convert(0, 32));
+        assert!(!PairRate::can_convert(0, 33));
+        assert!(!PairRate::can_convert(39, 38));
+        assert_eq!(PairRate::recommended_scaling(18, 6), None);
+        assert_eq!(PairRate::recommended_scaling(0, 32), None);
+        assert_eq!(PairRate::recommended_scaling(0, 33), Some(1));
+        assert_eq!(PairRate::recommended_scaling(38, 0), Some(6));
+        assert_eq!(PairRate::recommended_scaling(39, 0), None);
 
-        // 计算步骤：
-        // 1. 基础计算：1_000_000_000_000_000_000 * 3 / 1 = 3_000_000_000_000_000_000
-        // 2. 精度调整：精度相同，不需要调整
-        assert_eq!(output, 3_000_000_000_000_000_000);
+        // 按建议缩放后即可换算
+        let k = PairRate::recommended_scaling(36, 0).unwrap();
+        assert!(PairRate::can_convert(36 - k, 0));
+
+        let err = PairRate::adjust_decimals(1, 0, 36).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Decimal difference 36 exceeds maximum allowed 32, scale amounts by 10^4 first"
+        );
     }
 
     #[test]
-    fn test_output_higher_decimals() {
+    fn test_value_at_risk() {
         let price = PairRate::new(
             ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
             (1, 2),
-            (18, 24),
+            (6, 6),
         )
         .unwrap();
+        let input = 500_000_000;
 
-        // 输入1个token（18位精度）
-        let input_amount = 1_000_000_000_000_000_000u128; // 1 token
-        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
-
-        // 计算步骤：
-        // 1. 基础计算：1_000_000_000_000_000_000 * 2 / 1 = 2_000_000_000_000_000_000
-        // 2. 精度调整：从18位调整到24位，乘以10^6 = 2_000_000_000_000_000_000_000_000
-        assert_eq!(output, 2_000_000_000_000_000_000_000_000);
-    }
-
-    #[test]
-    fn test_adjust_decimals() {
-        // 测试精度调整函数
+        // 输出 10^9，波动率 1%：z = 1.6449 / 2.3263
+        assert_eq!(price.value_at_risk(input, 100, 9_500), Ok(16_449_000));
+        assert_eq!(price.value_at_risk(input, 100, 9_900), Ok(23_263_000));
 
-        // 从高精度到低精度
-        let result = PairRate::adjust_decimals(1_000_000_000_000_000_000_000_000, 24, 18).unwrap();
-        assert_eq!(result, 1_000_000_000_000_000_000);
+        for confidence in [9_500, 9_900] {
+            let low = price.value_at_risk(input, 100, confidence).unwrap();
+            let high = price.value_at_risk(input, 500, confidence).unwrap();
+            assert!(high > low);
+        }
+        assert_eq!(price.value_at_risk(input, 0, 9_500), Ok(0));
 
-        // 从低精度到高精度
-        let result = PairRate::adjust_decimals(1_000_000_000_000_000_000, 18, 24).unwrap();
-        assert_eq!(result, 1_000_000_000_000_000_000_000_000);
+        // 损失不超过全部输出
+        assert_eq!(price.value_at_risk(input, 10_000, 9_990), Ok(1_000_000_000));
 
-        // 相同精度
-        let result = PairRate::adjust_decimals(1_000_000_000_000_000_000, 18, 18).unwrap();
-        assert_eq!(result, 1_000_000_000_000_000_000);
+        assert!(price.value_at_risk(input, 100, 9_000).is_ok());
+        assert!(price.value_at_risk(input, 100, 9_800).is_err());
+        assert!(price.value_at_risk(0, 100, 9_500).is_err());
     }
 
     #[test]
-    fn test_price_consistency() {
-        let price = PairRate {
-            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            rate: (3, 5), // 3 input_token = 5 output_token
-            decimals: (24, 18),
-        };
-
-        // 测试往返计算的一致性
-        let input_amount = 3_000_000_000_000_000_000_000_000u128; // 3 tokens with 24 decimals
-        let output = PairRate::calculate_output_amount(&price, input_amount).unwrap();
-        let back_to_input = PairRate::calculate_input_amount(&price, output).unwrap();
+    fn test_new_unchecked() {
+        const RATE: PairRate = PairRate::new_unchecked((1, 2), (18, 18));
+        assert_eq!(RATE.rate, (1, 2));
+        assert_eq!(RATE.decimals, (18, 18));
+        assert_eq!(PairRate::calculate_output_amount(&RATE, 1_000), Ok(2_000));
 
-        assert_eq!(back_to_input, input_amount);
+        // 不做校验，错误在计算时才暴露
+        const INVALID: PairRate = PairRate::new_unchecked((0, 1), (18, 18));
+        assert_eq!(
+            PairRate::calculate_output_amount(&INVALID, 1_000),
+            Err(PairRateError::ZeroRate)
+        );
     }
 
     #[test]
-    fn test_error_handling() {
-        let price = PairRate {
-            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            rate: (1, 1),
-            decimals: (18, 18),
-        };
+    fn test_max_rate_boundary() {
+        let pair =
+            |rate| PairRate::new(("TOKEN_A".to_string(), "TOKEN_B".to_string()), rate, (0, 0));
 
-        // 测试输入金额为0
-        let result = PairRate::calculate_output_amount(&price, 0);
-        assert!(result.is_err());
+        // 汇率恰好为 MAX_RATE 时构造成功，且最小有效输入可以换算
+        let price = pair((MAX_RATE, 1)).unwrap();
+        assert_eq!(PairRate::calculate_output_amount(&price, MAX_RATE), Ok(1));
         assert_eq!(
-            result.unwrap_err(),
-            "Input amount must be greater than 0".to_string()
+            PairRate::calculate_output_amount(&price, MAX_RATE - 1),
+            Err(PairRateError::ZeroCalculatedOutput)
         );
+        assert_eq!(PairRate::calculate_input_amount(&price, 1), Ok(MAX_RATE));
 
-        // 测试输出金额为0
-        let result = PairRate::calculate_input_amount(&price, 0);
-        assert!(result.is_err());
+        let price = pair((1, MAX_RATE)).unwrap();
+        assert_eq!(PairRate::calculate_output_amount(&price, 1), Ok(MAX_RATE));
         assert_eq!(
-            result.unwrap_err(),
-            "Output amount must be greater than 0".to_string()
+            PairRate::calculate_output_amount(&price, 2),
+            Ok(2 * MAX_RATE)
         );
+        assert_eq!(PairRate::calculate_input_amount(&price, MAX_RATE), Ok(1));
 
-        // 测试汇率为0
-        let invalid_price = PairRate {
-            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            rate: (0, 1),
-            decimals: (18, 18),
-        };
-        let result = PairRate::calculate_output_amount(&invalid_price, 1000);
-        assert!(result.is_err());
+        let price = pair((MAX_RATE, MAX_RATE)).unwrap();
+        assert_eq!(PairRate::calculate_output_amount(&price, 1), Ok(1));
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, MAX_RATE),
+            Ok(MAX_RATE)
+        );
         assert_eq!(
-            result.unwrap_err(),
-            "Rate components must be greater than 0".to_string()
+            PairRate::calculate_input_amount(&price, MAX_RATE),
+            Ok(MAX_RATE)
         );
+        assert_eq!(
+            PairRate::calculate_output_amount_capacity(&price, MAX_RATE + 1),
+            Err(CapacityError {
+                max_input: MAX_RATE,
+                excess: 1
+            })
+        );
+
+        // 输入数量恰好为 MAX_RATE
+        let price = pair((1, 2)).unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, MAX_RATE),
+            Ok(u128::MAX - 1)
+        );
+        assert!(pair((MAX_RATE + 1, 1)).is_err());
     }
 
     #[test]
-    fn test_edge_cases() {
-        let price = PairRate {
-            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            rate: (1, 1),
-            decimals: (18, 18),
-        };
-
-        // 测试非常大的数字（接近 u128 上限）
-        let large_number = u128::MAX / 2;
-        let result = PairRate::calculate_output_amount(&price, large_number);
-        assert!(result.is_ok());
-
-        // 测试非常小的数字
-        let small_number = 1u128;
-        let result = PairRate::calculate_output_amount(&price, small_number);
-        assert!(result.is_ok());
-
-        // 测试精度差异较大的情况
-        let price_high_precision = PairRate {
-            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            rate: (1, 1),
-            decimals: (6, 24),
+    fn test_overflow_stage() {
+        let stage = |result: Result<u128, PairRateError>| match result {
+            Err(PairRateError::Overflow { stage, .. }) => Some(stage),
+            _ => None,
         };
 
-        // 输入 1.0 token（6位精度）
-        let input = 1_000_000u128; // 1.0 with 6 decimals
-        let result = PairRate::calculate_output_amount(&price_high_precision, input);
-        assert!(result.is_ok());
-
-        // 期望输出：1.0 token（24位精度）
-        // 1_000_000 * 10^(24-6) = 1_000_000 * 10^18
-        let expected = input * 10u128.pow(18); // 将6位精度转换为24位精度
-        assert_eq!(result.unwrap(), expected);
-
-        // 测试反向转换
-        let result = PairRate::calculate_input_amount(&price_high_precision, expected);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), input); // 应该回到原始输入值
-    }
-
-    #[test]
-    fn test_helper_functions() {
-        // 测试 is_valid
-        let valid_price = PairRate::new(
+        // 操作数超出 MAX_RATE
+        let price = PairRate::new(
             ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (1, 1),
+            (2, 1),
             (18, 18),
         )
         .unwrap();
-        assert!(valid_price.is_valid());
-
-        // 测试无效汇率
-        let invalid_result = PairRate::new(
-            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (0, 1),
-            (18, 18),
+        assert_eq!(
+            stage(PairRate::calculate_output_amount(&price, MAX_RATE + 1)),
+            Some(OverflowStage::RatePrecheck)
         );
-        assert!(invalid_result.is_err());
 
-        // 测试超出最大汇率
-        let invalid_rate_result = PairRate::new(
+        // 乘除结果超出 u128
+        let price = PairRate::new(
             ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (MAX_RATE + 1, 1),
+            (1, 4),
             (18, 18),
+        )
+        .unwrap();
+        assert_eq!(
+            PairRate::calculate_output_amount(&price, MAX_RATE / 2 + 1),
+            Err(PairRateError::Overflow {
+                stage: OverflowStage::MultiplyDivide,
+                operand_bits: (127, 3),
+            })
         );
-        assert!(invalid_rate_result.is_err());
-
-        // 测试超出最大精度
-        let invalid_decimals_result = PairRate::new(
-            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (1, 1),
-            (MAX_DECIMALS + 1, 18),
+        assert_eq!(
+            stage(PairRate::calculate_input_amount(
+                &price.inverse(),
+                MAX_RATE / 2 + 1
+            )),
+            Some(OverflowStage::MultiplyDivide)
         );
-        assert!(invalid_decimals_result.is_err());
 
-        // 测试 get_price_rate
+        // 精度放大溢出
         let price = PairRate::new(
             ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (2, 5),
-            (18, 18),
+            (1, 1),
+            (0, 32),
         )
         .unwrap();
-        let ((rate_in, rate_out), (dec_in, dec_out)) = price.get_price_rate();
-        assert_eq!(rate_in, 2);
-        assert_eq!(rate_out, 5);
-        assert_eq!(dec_in, 18);
-        assert_eq!(dec_out, 18);
+        let error = PairRate::calculate_output_amount(&price, 10u128.pow(7)).unwrap_err();
+        assert_eq!(
+            error,
+            PairRateError::Overflow {
+                stage: OverflowStage::DecimalScaleUp,
+                operand_bits: (24, 107),
+            }
+        );
+        assert!(error.is_overflow());
+        assert_eq!(
+            error.to_string(),
+            "Decimal adjustment caused overflow (overflow at decimal scale-up step, operands of 24 and 107 bits)"
+        );
+    }
 
-        // 测试 human_readable_rate
-        // 1. 相同精度测试
-        let price1 = PairRate::new(
-            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (2, 5),
-            (18, 18),
-        )
-        .unwrap();
-        assert_eq!(price1.get_human_readable_rate(), 2.5);
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_rate_of_change() {
+        let pair = |rate| {
+            PairRate::new(
+                ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+                rate,
+                (18, 6),
+            )
+            .unwrap()
+        };
+        let earlier = pair((100, 200));
 
-        // 2. 不同精度测试 (input_decimals < output_decimals)
-        let price2 = PairRate::new(
+        // 100 秒内上涨 10%
+        let change = pair((100, 220)).rate_of_change(&earlier, 100).unwrap();
+        assert!((change - 0.001).abs() < 1e-12);
+
+        // 10 秒内下跌 5%
+        let change = pair((100, 190)).rate_of_change(&earlier, 10).unwrap();
+        assert!((change + 0.005).abs() < 1e-12);
+
+        assert_eq!(earlier.rate_of_change(&pair((1, 2)), 60), Ok(0.0));
+        assert!(earlier.rate_of_change(&earlier, 0).is_err());
+        assert!(earlier.rate_of_change(&earlier.inverse(), 60).is_err());
+    }
+
+    #[test]
+    fn test_quote() {
+        let price = PairRate::new(
             ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (1, 1),
-            (6, 9),
+            (3, 1),
+            (6, 6),
         )
         .unwrap();
-        assert_eq!(price2.get_human_readable_rate(), 0.001); // 1 * 10^-3
 
-        // 3. 不同精度测试 (input_decimals > output_decimals)
-        let price3 = PairRate::new(
+        // 10 / 3 截断为 3，只需 9 即可换得
+        assert_eq!(
+            PairRate::quote(&price, 10),
+            Ok(Quote {
+                input_amount: 10,
+                output_amount: 3,
+                effective_rate: (10, 3),
+                dust: 1,
+            })
+        );
+        let quote = PairRate::quote(&price, 9_000).unwrap();
+        assert_eq!(quote.effective_rate, (3, 1));
+        assert_eq!(quote.dust, 0);
+
+        // 精度降低时实际比率与名义汇率不同
+        let price = PairRate::new(
             ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            (1, 1),
-            (9, 6),
+            (10, 19),
+            (18, 6),
         )
         .unwrap();
-        assert_eq!(price3.get_human_readable_rate(), 1000.0); // 1 * 10^3
-
-        // 测试默认值
-        let default_price = PairRate::default();
-        assert_eq!(default_price.rate, (1, 1));
-        assert_eq!(default_price.decimals, (18, 18));
-        assert_eq!(default_price.token_pair.0, "TOKEN_A");
-        assert_eq!(default_price.token_pair.1, "TOKEN_B");
-        assert!(default_price.is_valid());
-    }
-
-    #[test]
-    fn test_overflow_handling() {
-        let price = PairRate {
-            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            rate: (1, u128::MAX),
-            decimals: (18, 18),
-        };
-
-        // 测试乘法溢出
-        let result = PairRate::calculate_output_amount(&price, u128::MAX);
-        assert!(result.is_err());
+        let quote = PairRate::quote(&price, 1_000_000_000_000_123).unwrap();
+        assert_eq!(quote.output_amount, 1_900);
+        assert_eq!(quote.effective_rate, (1_000_000_000_000_123, 1_900));
+        assert_eq!(
+            quote.dust,
+            1_000_000_000_000_123 - PairRate::calculate_input_amount(&price, 1_900).unwrap()
+        );
 
-        // 测试精度调整溢出
-        let price_high_precision = PairRate {
-            token_pair: ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
-            rate: (1, 1),
-            decimals: (0, 38), // 超过最大安全精度
-        };
-        let result = PairRate::calculate_output_amount(&price_high_precision, u128::MAX / 2);
-        assert!(result.is_err());
+        assert_eq!(
+            PairRate::quote(&price, 0),
+            Err(PairRateError::ZeroInputAmount)
+        );
     }
 }
@@ -0,0 +1,159 @@
+//! 不依赖 BigUint 的定宽整数运算
+
+const LOW_MASK: u128 = u64::MAX as u128;
+
+/// 计算 `a * b / c`（向下取整），结果超出 u128 或 `c == 0` 时返回 `None`
+///
+/// 先尝试直接相乘；溢出时按 `a = q * c + r` 拆分为 `q * b + r * b / c`，
+/// 只有 `r * b` 仍然溢出时才退回到 256 位长除法。
+pub(crate) fn mul_div_floor(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    if let Some(product) = a.checked_mul(b) {
+        return Some(product / c);
+    }
+
+    let (quotient, remainder) = (a / c, a % c);
+    let whole = quotient.checked_mul(b)?;
+    let part = match remainder.checked_mul(b) {
+        Some(product) => product / c,
+        None => {
+            let (hi, lo) = widening_mul(remainder, b);
+            div_wide(hi, lo, c).0
+        }
+    };
+    whole.checked_add(part)
+}
+
+/// 计算 `a * b / c`（向上取整），结果超出 u128 或 `c == 0` 时返回 `None`
+pub(crate) fn mul_div_ceil(a: u128, b: u128, c: u128) -> Option<u128> {
+    let (quotient, remainder) = mul_div_rem(a, b, c)?;
+    if remainder == 0 {
+        Some(quotient)
+    } else {
+        quotient.checked_add(1)
+    }
+}
+
+/// 计算 `a * b` 除以 `c` 的商和余数，商超出 u128 或 `c == 0` 时返回 `None`
+pub(crate) fn mul_div_rem(a: u128, b: u128, c: u128) -> Option<(u128, u128)> {
+    if c == 0 {
+        return None;
+    }
+    if let Some(product) = a.checked_mul(b) {
+        return Some((product / c, product % c));
+    }
+
+    let (hi, lo) = widening_mul(a, b);
+    if hi >= c {
+        return None;
+    }
+    Some(div_wide(hi, lo, c))
+}
+
+/// SplitMix64 伪随机数：相同种子总是得到相同输出
+pub(crate) fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// 128 位乘法，返回 256 位结果的 (高位, 低位)
+pub(crate) fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    let (a_hi, a_lo) = (a >> 64, a & LOW_MASK);
+    let (b_hi, b_lo) = (b >> 64, b & LOW_MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_lo = a_hi * b_lo;
+    let hi_hi = a_hi * b_hi;
+
+    let mid = (lo_lo >> 64) + (lo_hi & LOW_MASK) + (hi_lo & LOW_MASK);
+    let lo = (lo_lo & LOW_MASK) | (mid << 64);
+    let hi = hi_hi + (lo_hi >> 64) + (hi_lo >> 64) + (mid >> 64);
+    (hi, lo)
+}
+
+/// 256 位数的有效位数
+pub(crate) fn bit_length_wide(hi: u128, lo: u128) -> u32 {
+    if hi != 0 {
+        256 - hi.leading_zeros()
+    } else {
+        128 - lo.leading_zeros()
+    }
+}
+
+/// 256 位数右移 `shift` 位
+pub(crate) fn shr_wide(hi: u128, lo: u128, shift: u32) -> (u128, u128) {
+    match shift {
+        0 => (hi, lo),
+        1..=127 => (hi >> shift, (lo >> shift) | (hi << (128 - shift))),
+        128..=255 => (0, hi >> (shift - 128)),
+        _ => (0, 0),
+    }
+}
+
+/// 256 位被除数除以 128 位除数，返回 (商, 余数)
+///
+/// 要求 `hi < divisor` 以保证商不超出 u128。
+fn div_wide(hi: u128, lo: u128, divisor: u128) -> (u128, u128) {
+    debug_assert!(hi < divisor);
+
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient |= 1;
+        }
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div_floor() {
+        // 直接相乘不溢出
+        assert_eq!(mul_div_floor(10, 19, 10), Some(19));
+        assert_eq!(mul_div_floor(7, 3, 2), Some(10));
+
+        // 需要拆分或 256 位长除法
+        assert_eq!(
+            mul_div_floor(u128::MAX, u128::MAX, u128::MAX),
+            Some(u128::MAX)
+        );
+        assert_eq!(mul_div_floor(u128::MAX, 3, 6), Some(u128::MAX / 2));
+        assert_eq!(
+            mul_div_floor(u128::MAX / 3, 1 << 100, 1 << 101),
+            Some(u128::MAX / 6)
+        );
+
+        // 真正的溢出与除零
+        assert_eq!(mul_div_floor(u128::MAX, 2, 1), None);
+        assert_eq!(mul_div_floor(1, 1, 0), None);
+
+        assert_eq!(widening_mul(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+
+        // 向上取整与余数
+        assert_eq!(mul_div_ceil(7, 3, 2), Some(11));
+        assert_eq!(mul_div_ceil(u128::MAX, 4, 4), Some(u128::MAX));
+        assert_eq!(
+            mul_div_rem(u128::MAX, 3, 4),
+            Some((u128::MAX / 4 * 3 + 2, 1))
+        );
+        assert_eq!(mul_div_rem(u128::MAX, 3, 2), None);
+
+        // 256 位辅助运算
+        let (hi, lo) = widening_mul(u128::MAX, 10);
+        assert_eq!(bit_length_wide(hi, lo), 132);
+        assert_eq!(shr_wide(hi, lo, 4), (0, u128::MAX / 8 * 5 + 4));
+    }
+}
@@ -0,0 +1,69 @@
+//! `serde` 反序列化时的校验
+
+use alloc::string::String;
+use serde::{Deserialize, Deserializer};
+
+use crate::{PairRate, PairRateError};
+
+/// 反序列化的中间表示，转换为 `PairRate` 时执行与 `new` 相同的校验
+#[derive(Deserialize)]
+pub struct PairRateRepr {
+    token_pair: (String, String),
+    rate: (u128, u128),
+    decimals: (u8, u8),
+}
+
+impl TryFrom<PairRateRepr> for PairRate {
+    type Error = PairRateError;
+
+    fn try_from(repr: PairRateRepr) -> Result<Self, Self::Error> {
+        PairRate::new(repr.token_pair, repr.rate, repr.decimals)
+    }
+}
+
+/// 只为 `PairRate<u128>` 实现，经由 `PairRateRepr` 执行校验
+impl<'de> Deserialize<'de> for PairRate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PairRateRepr::deserialize(deserializer)?;
+        PairRate::try_from(repr).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_round_trip() {
+        let price = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (10, 19),
+            (18, 6),
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&price).unwrap();
+        assert_eq!(
+            json,
+            r#"{"token_pair":["TOKEN_A","TOKEN_B"],"rate":[10,19],"decimals":[18,6]}"#
+        );
+        assert_eq!(serde_json::from_str::<PairRate>(&json).unwrap(), price);
+
+        // 反序列化时执行与 new 相同的校验
+        let error = serde_json::from_str::<PairRate>(
+            r#"{"token_pair":["TOKEN_A","TOKEN_B"],"rate":[0,1],"decimals":[18,18]}"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Rate components must be greater than 0"));
+
+        let error = serde_json::from_str::<PairRate>(
+            r#"{"token_pair":["TOKEN_A","TOKEN_B"],"rate":[1,1],"decimals":[39,18]}"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Input decimals 39 exceeds maximum allowed 38"));
+    }
+}
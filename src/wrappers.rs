@@ -0,0 +1,262 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{math, PairRate, PairRateError, PriceBook, BPS_DENOMINATOR};
+
+/// 带累计输入上限的交易对（如每日兑换额度）
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeCappedPair {
+    pub pair: PairRate,
+    /// 允许的累计输入上限
+    pub cap: u128,
+    /// 已使用的累计输入
+    pub used: u128,
+}
+
+impl VolumeCappedPair {
+    /// 创建额度未使用的交易对
+    pub fn new(pair: PairRate, cap: u128) -> Self {
+        Self { pair, cap, used: 0 }
+    }
+
+    /// 剩余可用的输入额度
+    pub fn remaining(&self) -> u128 {
+        self.cap.saturating_sub(self.used)
+    }
+
+    /// 换算并累计输入，超出额度时拒绝且不改变已用额度
+    pub fn convert(&mut self, input_amount: u128) -> Result<u128, String> {
+        let remaining = self.remaining();
+        if input_amount > remaining {
+            return Err(format!(
+                "Input amount {} exceeds volume cap, remaining capacity {}",
+                input_amount, remaining
+            ));
+        }
+
+        let output_amount = PairRate::calculate_output_amount(&self.pair, input_amount)?;
+        self.used += input_amount;
+        Ok(output_amount)
+    }
+
+    /// 重置已用额度（如进入新的统计周期）
+    pub fn reset(&mut self) {
+        self.used = 0;
+    }
+}
+
+/// 汇率只能上调的交易对（如赎回汇率）
+///
+/// 内部交易对不公开可变访问，汇率只能通过 `update_rate` 更新。
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonotonicPair {
+    pair: PairRate,
+}
+
+impl MonotonicPair {
+    /// 以当前交易对作为初始汇率
+    pub fn new(pair: PairRate) -> Self {
+        Self { pair }
+    }
+
+    /// 当前交易对
+    pub fn pair(&self) -> &PairRate {
+        &self.pair
+    }
+
+    /// 更新汇率 `(输入比率, 输出比率)`，新汇率低于当前汇率时拒绝且不做修改
+    pub fn update_rate(&mut self, new_rate: (u128, u128)) -> Result<(), String> {
+        PairRate::validate_rate(new_rate)?;
+
+        // new.1 / new.0 < old.1 / old.0 <=> new.1 * old.0 < old.1 * new.0
+        let (old_input, old_output) = self.pair.rate;
+        if math::widening_mul(new_rate.1, old_input) < math::widening_mul(old_output, new_rate.0) {
+            return Err(format!(
+                "Rate update {:?} would lower the current rate {:?}",
+                new_rate, self.pair.rate
+            ));
+        }
+
+        self.pair.rate = new_rate;
+        Ok(())
+    }
+
+    /// 按当前汇率换算
+    pub fn convert(&self, input_amount: u128) -> Result<u128, PairRateError> {
+        PairRate::calculate_output_amount(&self.pair, input_amount)
+    }
+}
+
+/// 汇率被限制在近期移动平均附近的交易对（简易熔断）
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandedPair {
+    /// 最近一次换算使用的交易对
+    pub pair: PairRate,
+    /// 允许偏离移动平均的基点数
+    pub band_bps: u16,
+    window: Vec<(u128, u128)>,
+    window_size: usize,
+}
+
+impl BandedPair {
+    /// 以 `pair` 的汇率作为窗口中的第一个样本
+    ///
+    /// `band_bps` 须小于 `BPS_DENOMINATOR`（10000），`window_size` 至少为 1。
+    pub fn new(pair: PairRate, window_size: usize, band_bps: u16) -> Result<Self, String> {
+        if window_size == 0 {
+            return Err("Window size must be greater than 0".to_string());
+        }
+        if band_bps >= BPS_DENOMINATOR {
+            return Err(format!(
+                "Band {} bps must be less than {}",
+                band_bps, BPS_DENOMINATOR
+            ));
+        }
+        PairRate::validate_rate(pair.rate)?;
+
+        let window = Vec::from([pair.rate]);
+        Ok(Self {
+            pair,
+            band_bps,
+            window,
+            window_size,
+        })
+    }
+
+    /// 窗口内汇率的算术平均
+    pub fn moving_average(&self) -> Result<(u128, u128), String> {
+        PriceBook::average_rate(&self.window)
+    }
+
+    /// 将 `current_rate` 限制在移动平均 ±`band_bps` 基点内后换算，并把限制后的汇率计入窗口
+    ///
+    /// 换算失败时窗口与交易对保持不变。
+    pub fn convert(
+        &mut self,
+        input_amount: u128,
+        current_rate: (u128, u128),
+    ) -> Result<u128, String> {
+        PairRate::validate_rate(current_rate)?;
+        let average = self.moving_average()?;
+        let (band, denominator) = (self.band_bps as u128, BPS_DENOMINATOR as u128);
+        let lower = PairRate::multiply_rate(average, (denominator, denominator - band))?;
+        let upper = PairRate::multiply_rate(average, (denominator, denominator + band))?;
+
+        let rate = if Self::less_than(current_rate, lower) {
+            lower
+        } else if Self::less_than(upper, current_rate) {
+            upper
+        } else {
+            current_rate
+        };
+
+        let pair = PairRate {
+            rate,
+            ..self.pair.clone()
+        };
+        let output_amount = PairRate::calculate_output_amount(&pair, input_amount)?;
+
+        self.pair = pair;
+        if self.window.len() == self.window_size {
+            self.window.remove(0);
+        }
+        self.window.push(rate);
+        Ok(output_amount)
+    }
+
+    /// 汇率（输出/输入）`a < b`
+    fn less_than(a: (u128, u128), b: (u128, u128)) -> bool {
+        math::widening_mul(a.1, b.0) < math::widening_mul(b.1, a.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_capped_pair() {
+        let pair = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 2),
+            (18, 18),
+        )
+        .unwrap();
+        let mut capped = VolumeCappedPair::new(pair, 1_000);
+
+        assert_eq!(capped.convert(600).unwrap(), 1_200);
+        assert_eq!(capped.convert(400).unwrap(), 800);
+        assert_eq!(capped.used, 1_000);
+
+        let err = capped.convert(1).unwrap_err();
+        assert!(err.contains("remaining capacity 0"));
+        assert_eq!(capped.used, 1_000);
+
+        capped.reset();
+        assert_eq!(capped.remaining(), 1_000);
+        assert!(capped
+            .convert(1_001)
+            .unwrap_err()
+            .contains("remaining capacity 1000"));
+    }
+
+    #[test]
+    fn test_monotonic_pair() {
+        let pair = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (100, 105),
+            (18, 18),
+        )
+        .unwrap();
+        let mut monotonic = MonotonicPair::new(pair);
+        assert_eq!(monotonic.convert(1_000).unwrap(), 1_050);
+
+        // 上调与持平均可接受
+        monotonic.update_rate((100, 106)).unwrap();
+        monotonic.update_rate((50, 53)).unwrap();
+        assert_eq!(monotonic.pair().rate, (50, 53));
+        assert_eq!(monotonic.convert(1_000).unwrap(), 1_060);
+
+        // 下调被拒绝，汇率保持不变
+        let err = monotonic.update_rate((1_000, 1_059)).unwrap_err();
+        assert!(err.contains("would lower"));
+        assert!(monotonic.update_rate((0, 1)).is_err());
+        assert_eq!(monotonic.pair().rate, (50, 53));
+    }
+
+    #[test]
+    fn test_banded_pair() {
+        let pair = PairRate::new(
+            ("TOKEN_A".to_string(), "TOKEN_B".to_string()),
+            (1, 100),
+            (18, 18),
+        )
+        .unwrap();
+        let mut banded = BandedPair::new(pair, 3, 500).unwrap();
+
+        // 带内的汇率原样使用
+        assert_eq!(banded.convert(10, (1, 102)).unwrap(), 1_020);
+        assert_eq!(banded.moving_average().unwrap(), (1, 101));
+
+        // 尖峰被限制在平均值 101 的 +5% 处
+        assert_eq!(banded.convert(100, (1, 200)).unwrap(), 10_605);
+        assert_eq!(banded.pair.rate, (20, 2_121));
+        // 窗口 [100, 102, 106.05] 的平均值
+        assert_eq!(banded.moving_average().unwrap(), (60, 6_161));
+
+        // 窗口已满，最早的样本被移出；向下的尖峰被限制在 -5% 处
+        banded.convert(1, (1, 1)).unwrap();
+        assert_eq!(banded.window.len(), 3);
+        assert_eq!(banded.window[0], (1, 102));
+        assert_eq!(banded.pair.rate, (1_200, 117_059));
+
+        // 换算失败时窗口不变
+        let window = banded.window.clone();
+        assert!(banded.convert(0, (1, 100)).is_err());
+        assert_eq!(banded.window, window);
+
+        assert!(BandedPair::new(banded.pair.clone(), 0, 500).is_err());
+        assert!(BandedPair::new(banded.pair.clone(), 3, 10_000).is_err());
+    }
+}